@@ -0,0 +1,735 @@
+use arboard::{Clipboard, ImageData};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::wayland;
+
+/// Longest wait for the target app to read a pasted transcript.
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Wait after pressing paste where reading the clipboard cannot be observed.
+const SETTLE_DELAY: Duration = Duration::from_millis(150);
+
+/// What the clipboard held. On X11 that is every format the owner offered;
+/// elsewhere, the formats arboard can read back.
+#[derive(Default)]
+pub struct Contents {
+    text: Option<String>,
+    html: Option<String>,
+    image: Option<ImageData<'static>>,
+    files: Option<Vec<PathBuf>>,
+    #[cfg(target_os = "linux")]
+    x11: Option<x11::Snapshot>,
+}
+
+impl Contents {
+    pub fn text(text: &str) -> Self {
+        Self {
+            text: Some(text.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// Snapshot the clipboard. Formats that cannot be read are left out.
+    pub fn read(clipboard: &mut Clipboard) -> Self {
+        #[cfg(target_os = "linux")]
+        if !wayland::is_session() {
+            match x11::Snapshot::take() {
+                Ok(Some(snapshot)) => {
+                    return Self {
+                        x11: Some(snapshot),
+                        ..Default::default()
+                    }
+                }
+                Ok(None) => return Self::default(),
+                Err(e) => eprintln!(
+                    "Cannot copy every clipboard format, keeping text, HTML, images and files: {}",
+                    e
+                ),
+            }
+        }
+
+        Self {
+            text: clipboard.get().text().ok(),
+            html: clipboard.get().html().ok(),
+            image: clipboard.get().image().ok(),
+            files: clipboard.get().file_list().ok().filter(|f| !f.is_empty()),
+            ..Default::default()
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        #[cfg(target_os = "linux")]
+        if self.x11.is_some() {
+            return false;
+        }
+        self.text.is_none() && self.html.is_none() && self.image.is_none() && self.files.is_none()
+    }
+
+    /// Put the contents back on the clipboard. An X11 snapshot is served in
+    /// every format it was copied in. Otherwise arboard sets one kind of data
+    /// at a time, so the richest one wins: files, then an image, then HTML
+    /// with its plain text, then text. An empty snapshot changes nothing.
+    pub fn write(self) -> Result<(), String> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(snapshot) = self.x11 {
+            return snapshot.restore();
+        }
+
+        // X11 and Wayland serve the clipboard from the owning process, so the
+        // contents only outlive this call if a thread keeps serving them
+        #[cfg(target_os = "linux")]
+        {
+            let mut clipboard = match Clipboard::new() {
+                Ok(clipboard) => clipboard,
+                Err(_) if wayland::is_session() => {
+                    return match self.text {
+                        Some(text) => wayland::copy_text(&text),
+                        None => Ok(()),
+                    }
+                }
+                Err(e) => return Err(format!("Clipboard init error: {}", e)),
+            };
+            std::thread::spawn(move || {
+                use arboard::SetExtLinux;
+                if let Err(e) = self.set(clipboard.set().wait()) {
+                    eprintln!("{}", e);
+                }
+            });
+            Ok(())
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            let mut clipboard =
+                Clipboard::new().map_err(|e| format!("Clipboard init error: {}", e))?;
+            self.set(clipboard.set())
+        }
+    }
+
+    fn set(self, set: arboard::Set<'_>) -> Result<(), String> {
+        let result = if let Some(files) = self.files {
+            set.file_list(&files)
+        } else if let Some(image) = self.image {
+            set.image(image)
+        } else if let Some(html) = self.html {
+            set.html(html, self.text)
+        } else if let Some(text) = self.text {
+            set.text(text)
+        } else {
+            return Ok(());
+        };
+        result.map_err(|e| format!("Clipboard set error: {}", e))
+    }
+}
+
+/// Put `text` on the clipboard, call `press` to paste it, and return once
+/// the target app has read it, so the caller can restore the clipboard
+/// without the app pasting the old contents instead.
+pub fn paste_text(
+    clipboard: &mut Clipboard,
+    text: &str,
+    press: &dyn Fn() -> Result<(), String>,
+) -> Result<(), String> {
+    #[cfg(target_os = "linux")]
+    match x11::TextOffer::new(text) {
+        Ok(offer) => return offer.paste(press),
+        // Expected on Wayland without XWayland; anything else is worth a note
+        Err(e) if std::env::var_os("DISPLAY").is_some() => {
+            eprintln!("Cannot watch the X11 clipboard, pasting blind: {}", e)
+        }
+        Err(_) => {}
+    }
+
+    clipboard
+        .set_text(text)
+        .map_err(|e| format!("Clipboard set error: {}", e))?;
+    // Small delay to ensure clipboard is ready
+    std::thread::sleep(Duration::from_millis(50));
+    press()?;
+    std::thread::sleep(SETTLE_DELAY);
+    Ok(())
+}
+
+/// [`paste_text`] for Wayland compositors arboard cannot reach: the text goes
+/// through wl-copy, and only a previous text is put back.
+pub fn paste_text_wl(text: &str, press: &dyn Fn() -> Result<(), String>) -> Result<(), String> {
+    let previous = wayland::read_text();
+    let mut offer = wayland::copy_text_once(text)?;
+    std::thread::sleep(Duration::from_millis(50));
+    let pasted = press();
+    // wl-copy exits once the target app has read the text
+    if pasted.is_ok() && !wayland::wait_exit(&mut offer, READ_TIMEOUT) {
+        eprintln!("The target app did not read the clipboard within {:?}", READ_TIMEOUT);
+    }
+    let _ = offer.kill();
+    let _ = offer.wait();
+    if let Some(previous) = previous {
+        if let Err(e) = wayland::copy_text(&previous) {
+            eprintln!("Failed to restore the clipboard: {}", e);
+        }
+    }
+    pasted
+}
+
+// ── X11: serve the transcript ourselves to see when it is read ──
+
+#[cfg(target_os = "linux")]
+mod x11 {
+    use std::time::{Duration, Instant};
+    use x11rb::connection::{Connection, RequestConnection as _};
+    use x11rb::protocol::xproto::{
+        Atom, AtomEnum, ConnectionExt as _, CreateWindowAux, EventMask, PropMode, Property,
+        SelectionNotifyEvent, SelectionRequestEvent, Window, WindowClass, SELECTION_NOTIFY_EVENT,
+    };
+    use x11rb::protocol::Event;
+    use x11rb::rust_connection::RustConnection;
+    use x11rb::wrapper::ConnectionExt as _;
+    use x11rb::{COPY_DEPTH_FROM_PARENT, CURRENT_TIME, NONE};
+
+    use super::{READ_TIMEOUT, SETTLE_DELAY};
+
+    fn x11_error(e: impl std::fmt::Display) -> String {
+        format!("X11 error: {}", e)
+    }
+
+    fn intern(conn: &RustConnection, name: &[u8]) -> Result<Atom, String> {
+        Ok(conn
+            .intern_atom(false, name)
+            .map_err(x11_error)?
+            .reply()
+            .map_err(x11_error)?
+            .atom)
+    }
+
+    /// An invisible window to own the clipboard or receive its contents.
+    fn hidden_window(conn: &RustConnection, screen_num: usize) -> Result<Window, String> {
+        let screen = &conn.setup().roots[screen_num];
+        let window: Window = conn.generate_id().map_err(x11_error)?;
+        conn.create_window(
+            COPY_DEPTH_FROM_PARENT,
+            window,
+            screen.root,
+            0,
+            0,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_OUTPUT,
+            screen.root_visual,
+            // Large transfers are announced through property changes
+            &CreateWindowAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+        )
+        .map_err(x11_error)?;
+        Ok(window)
+    }
+
+    /// Make `window` the owner of `selection`.
+    fn claim(conn: &RustConnection, window: Window, selection: Atom) -> Result<(), String> {
+        conn.set_selection_owner(window, selection, CURRENT_TIME)
+            .map_err(x11_error)?;
+        let owner = conn
+            .get_selection_owner(selection)
+            .map_err(x11_error)?
+            .reply()
+            .map_err(x11_error)?
+            .owner;
+        if owner != window {
+            return Err("could not take the clipboard".to_string());
+        }
+        Ok(())
+    }
+
+    /// Tell the requestor its request was answered in `property`, or refused
+    /// if that is `NONE`.
+    fn notify(
+        conn: &RustConnection,
+        request: &SelectionRequestEvent,
+        property: Atom,
+    ) -> Result<(), String> {
+        let notify = SelectionNotifyEvent {
+            response_type: SELECTION_NOTIFY_EVENT,
+            sequence: 0,
+            time: request.time,
+            requestor: request.requestor,
+            selection: request.selection,
+            target: request.target,
+            property,
+        };
+        conn.send_event(false, request.requestor, EventMask::NO_EVENT, notify)
+            .map_err(x11_error)?;
+        conn.flush().map_err(x11_error)
+    }
+
+    enum Outcome {
+        Read,
+        /// Another client took the clipboard, so reads are no longer seen
+        Lost,
+        TimedOut,
+    }
+
+    /// Text owned on the CLIPBOARD selection by a hidden window of ours.
+    pub struct TextOffer {
+        conn: RustConnection,
+        text: Vec<u8>,
+        /// Window of the clipboard manager, whose reads are not the paste
+        manager: Window,
+        clipboard: Atom,
+        targets: Atom,
+        utf8_string: Atom,
+        text_atom: Atom,
+        text_plain: Atom,
+    }
+
+    impl TextOffer {
+        pub fn new(text: &str) -> Result<Self, String> {
+            let (conn, screen_num) = x11rb::connect(None).map_err(x11_error)?;
+            // Larger text needs the INCR protocol; leave it to arboard
+            if text.len() + 1024 > conn.maximum_request_bytes() {
+                return Err("text too long for a single X11 request".to_string());
+            }
+            let window = hidden_window(&conn, screen_num)?;
+            let manager = conn
+                .get_selection_owner(intern(&conn, b"CLIPBOARD_MANAGER")?)
+                .map_err(x11_error)?
+                .reply()
+                .map_err(x11_error)?
+                .owner;
+
+            let offer = Self {
+                manager,
+                clipboard: intern(&conn, b"CLIPBOARD")?,
+                targets: intern(&conn, b"TARGETS")?,
+                utf8_string: intern(&conn, b"UTF8_STRING")?,
+                text_atom: intern(&conn, b"TEXT")?,
+                text_plain: intern(&conn, b"text/plain;charset=utf-8")?,
+                text: text.as_bytes().to_vec(),
+                conn,
+            };
+
+            claim(&offer.conn, window, offer.clipboard)?;
+            Ok(offer)
+        }
+
+        /// Press paste and wait for the text to be read. The clipboard is
+        /// released when the offer is dropped.
+        pub fn paste(self, press: &dyn Fn() -> Result<(), String>) -> Result<(), String> {
+            // Clipboard managers copy new contents right away. Their reads
+            // never count as the paste, and nothing before the key press does
+            let settled = self.serve(Instant::now() + Duration::from_millis(50))?;
+            press()?;
+            let outcome = match settled {
+                Outcome::Lost => Outcome::Lost,
+                _ => self.serve(Instant::now() + READ_TIMEOUT)?,
+            };
+            match outcome {
+                Outcome::Read => {}
+                Outcome::Lost => std::thread::sleep(SETTLE_DELAY),
+                Outcome::TimedOut => {
+                    eprintln!("The target app did not read the clipboard within {:?}", READ_TIMEOUT)
+                }
+            }
+            Ok(())
+        }
+
+        /// Answer clipboard requests until the text is read, the clipboard
+        /// is taken over, or `deadline` passes.
+        fn serve(&self, deadline: Instant) -> Result<Outcome, String> {
+            while Instant::now() < deadline {
+                match self.conn.poll_for_event().map_err(x11_error)? {
+                    Some(Event::SelectionRequest(request)) => {
+                        if self.answer(&request)? {
+                            return Ok(Outcome::Read);
+                        }
+                    }
+                    Some(Event::SelectionClear(_)) => return Ok(Outcome::Lost),
+                    Some(_) => {}
+                    None => std::thread::sleep(Duration::from_millis(5)),
+                }
+            }
+            Ok(Outcome::TimedOut)
+        }
+
+        /// Reply to one request. Returns whether the text itself was sent to
+        /// a client other than the clipboard manager.
+        fn answer(&self, request: &SelectionRequestEvent) -> Result<bool, String> {
+            // Obsolete clients leave the property unset
+            let property = if request.property == NONE {
+                request.target
+            } else {
+                request.property
+            };
+            let string: Atom = AtomEnum::STRING.into();
+            let text_targets = [self.utf8_string, self.text_atom, self.text_plain, string];
+
+            let (property, read) = if request.target == self.targets {
+                let mut targets = vec![self.targets];
+                targets.extend_from_slice(&text_targets);
+                self.conn
+                    .change_property32(
+                        PropMode::REPLACE,
+                        request.requestor,
+                        property,
+                        AtomEnum::ATOM,
+                        &targets,
+                    )
+                    .map_err(x11_error)?;
+                (property, false)
+            } else if text_targets.contains(&request.target) {
+                // TEXT lets the owner pick the encoding
+                let kind = if request.target == self.text_atom {
+                    self.utf8_string
+                } else {
+                    request.target
+                };
+                self.conn
+                    .change_property8(PropMode::REPLACE, request.requestor, property, kind, &self.text)
+                    .map_err(x11_error)?;
+                (property, request.requestor != self.manager)
+            } else {
+                // Unsupported target: refuse
+                (NONE, false)
+            };
+
+            notify(&self.conn, request, property)?;
+            Ok(read)
+        }
+    }
+
+    /// Longest wait for the clipboard owner to answer one request.
+    const CONVERT_TIMEOUT: Duration = Duration::from_millis(500);
+
+    /// Targets that describe the selection or ask the owner to do something
+    /// instead of holding data.
+    const META_TARGETS: &[&[u8]] = &[
+        b"TARGETS",
+        b"MULTIPLE",
+        b"TIMESTAMP",
+        b"SAVE_TARGETS",
+        b"DELETE",
+        b"INSERT_SELECTION",
+        b"INSERT_PROPERTY",
+    ];
+
+    /// The clipboard in one format.
+    struct Data {
+        target: Atom,
+        /// Type of the property the data came in
+        kind: Atom,
+        /// 8, 16 or 32 bits per item
+        format: u8,
+        value: Vec<u8>,
+    }
+
+    /// Every format the CLIPBOARD owner offered, copied out so it can be
+    /// served again after a paste replaced the owner.
+    pub struct Snapshot {
+        conn: RustConnection,
+        window: Window,
+        clipboard: Atom,
+        targets: Atom,
+        incr: Atom,
+        /// Property of `window` the owner writes its answers to
+        property: Atom,
+        data: Vec<Data>,
+    }
+
+    /// Whether `data` can be served again in a single X11 request. Data in an
+    /// invalid format is skipped; larger data would need the INCR protocol,
+    /// so the whole snapshot is given up rather than silently losing it.
+    fn servable(data: &Data, max_len: usize) -> Result<bool, String> {
+        if !matches!(data.format, 8 | 16 | 32) {
+            return Ok(false);
+        }
+        if data.value.len() > max_len {
+            return Err(format!(
+                "a {} byte format is too large for a single X11 request",
+                data.value.len()
+            ));
+        }
+        Ok(true)
+    }
+
+    impl Snapshot {
+        /// Copy the clipboard in every format its owner offers. `None` when
+        /// the clipboard is empty.
+        pub fn take() -> Result<Option<Self>, String> {
+            let (conn, screen_num) = x11rb::connect(None).map_err(x11_error)?;
+            let mut snapshot = Self {
+                window: hidden_window(&conn, screen_num)?,
+                clipboard: intern(&conn, b"CLIPBOARD")?,
+                targets: intern(&conn, b"TARGETS")?,
+                incr: intern(&conn, b"INCR")?,
+                property: intern(&conn, b"LIGHT_WHISPER_CLIPBOARD")?,
+                data: Vec::new(),
+                conn,
+            };
+            let owner = snapshot
+                .conn
+                .get_selection_owner(snapshot.clipboard)
+                .map_err(x11_error)?
+                .reply()
+                .map_err(x11_error)?
+                .owner;
+            if owner == NONE {
+                return Ok(None);
+            }
+
+            let offered = snapshot
+                .convert(snapshot.targets)?
+                .filter(|d| d.format == 32)
+                .ok_or("the clipboard owner did not list its formats")?;
+            let meta = META_TARGETS
+                .iter()
+                .map(|name| intern(&snapshot.conn, name))
+                .collect::<Result<Vec<_>, _>>()?;
+            let max_len = snapshot.conn.maximum_request_bytes() - 1024;
+            for target in offered.value.chunks_exact(4) {
+                let target = u32::from_ne_bytes(target.try_into().unwrap());
+                if meta.contains(&target) || snapshot.data.iter().any(|d| d.target == target) {
+                    continue;
+                }
+                if let Some(data) = snapshot.convert(target)? {
+                    if servable(&data, max_len)? {
+                        snapshot.data.push(data);
+                    }
+                }
+            }
+            if snapshot.data.is_empty() {
+                return Err("the clipboard owner answered no format".to_string());
+            }
+            Ok(Some(snapshot))
+        }
+
+        /// Wait for the next event until `deadline`.
+        fn next_event(&self, deadline: Instant) -> Result<Option<Event>, String> {
+            while Instant::now() < deadline {
+                match self.conn.poll_for_event().map_err(x11_error)? {
+                    Some(event) => return Ok(Some(event)),
+                    None => std::thread::sleep(Duration::from_millis(2)),
+                }
+            }
+            Ok(None)
+        }
+
+        fn take_property(&self) -> Result<Data, String> {
+            let reply = self
+                .conn
+                .get_property(true, self.window, self.property, AtomEnum::ANY, 0, u32::MAX / 4)
+                .map_err(x11_error)?
+                .reply()
+                .map_err(x11_error)?;
+            Ok(Data {
+                target: NONE,
+                kind: reply.type_,
+                format: reply.format,
+                value: reply.value,
+            })
+        }
+
+        /// Ask the owner for the clipboard as `target`. `None` when it
+        /// refuses or does not answer in time.
+        fn convert(&self, target: Atom) -> Result<Option<Data>, String> {
+            self.conn
+                .convert_selection(self.window, self.clipboard, target, self.property, CURRENT_TIME)
+                .map_err(x11_error)?;
+            self.conn.flush().map_err(x11_error)?;
+
+            let deadline = Instant::now() + CONVERT_TIMEOUT;
+            loop {
+                match self.next_event(deadline)? {
+                    None => return Ok(None),
+                    Some(Event::SelectionNotify(e)) if e.target == target => {
+                        if e.property == NONE {
+                            return Ok(None);
+                        }
+                        break;
+                    }
+                    Some(_) => {}
+                }
+            }
+            let first = self.take_property()?;
+            if first.kind != self.incr {
+                return Ok(Some(Data { target, ..first }));
+            }
+
+            // INCR: deleting the property asks for the next chunk, and an
+            // empty chunk ends the transfer
+            let mut data = Data {
+                target,
+                kind: NONE,
+                format: 8,
+                value: Vec::new(),
+            };
+            loop {
+                let deadline = Instant::now() + CONVERT_TIMEOUT;
+                loop {
+                    match self.next_event(deadline)? {
+                        None => return Ok(None),
+                        Some(Event::PropertyNotify(e))
+                            if e.atom == self.property && e.state == Property::NEW_VALUE =>
+                        {
+                            break
+                        }
+                        Some(_) => {}
+                    }
+                }
+                let chunk = self.take_property()?;
+                if chunk.value.is_empty() {
+                    return Ok(Some(data));
+                }
+                data.kind = chunk.kind;
+                data.format = chunk.format;
+                data.value.extend_from_slice(&chunk.value);
+            }
+        }
+
+        /// Own the clipboard again and serve the snapshot from a background
+        /// thread until another client takes the clipboard over.
+        pub fn restore(self) -> Result<(), String> {
+            claim(&self.conn, self.window, self.clipboard)?;
+            std::thread::spawn(move || {
+                if let Err(e) = self.serve() {
+                    eprintln!("Stopped serving the restored clipboard: {}", e);
+                }
+            });
+            Ok(())
+        }
+
+        fn serve(&self) -> Result<(), String> {
+            loop {
+                match self.conn.wait_for_event().map_err(x11_error)? {
+                    Event::SelectionRequest(request) => self.answer(&request)?,
+                    Event::SelectionClear(_) => return Ok(()),
+                    _ => {}
+                }
+            }
+        }
+
+        fn answer(&self, request: &SelectionRequestEvent) -> Result<(), String> {
+            // Obsolete clients leave the property unset
+            let property = if request.property == NONE {
+                request.target
+            } else {
+                request.property
+            };
+            let property = if request.target == self.targets {
+                let mut targets = vec![self.targets];
+                targets.extend(self.data.iter().map(|d| d.target));
+                self.conn
+                    .change_property32(
+                        PropMode::REPLACE,
+                        request.requestor,
+                        property,
+                        AtomEnum::ATOM,
+                        &targets,
+                    )
+                    .map_err(x11_error)?;
+                property
+            } else if let Some(data) = self.data.iter().find(|d| d.target == request.target) {
+                self.conn
+                    .change_property(
+                        PropMode::REPLACE,
+                        request.requestor,
+                        property,
+                        data.kind,
+                        data.format,
+                        (data.value.len() / (data.format as usize / 8)) as u32,
+                        &data.value,
+                    )
+                    .map_err(x11_error)?;
+                property
+            } else {
+                NONE
+            };
+            notify(&self.conn, request, property)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /// Put `formats`, pairs of target name and text, on the clipboard.
+        fn own(formats: &[(&[u8], &str)]) -> Snapshot {
+            let (conn, screen_num) = x11rb::connect(None).unwrap();
+            let utf8_string = intern(&conn, b"UTF8_STRING").unwrap();
+            let data = formats
+                .iter()
+                .map(|(name, text)| Data {
+                    target: intern(&conn, name).unwrap(),
+                    kind: utf8_string,
+                    format: 8,
+                    value: text.as_bytes().to_vec(),
+                })
+                .collect();
+            Snapshot {
+                window: hidden_window(&conn, screen_num).unwrap(),
+                clipboard: intern(&conn, b"CLIPBOARD").unwrap(),
+                targets: intern(&conn, b"TARGETS").unwrap(),
+                incr: intern(&conn, b"INCR").unwrap(),
+                property: intern(&conn, b"LIGHT_WHISPER_CLIPBOARD").unwrap(),
+                data,
+                conn,
+            }
+        }
+
+        fn formats(snapshot: &Snapshot) -> Vec<(String, String)> {
+            snapshot
+                .data
+                .iter()
+                .map(|d| {
+                    let name = snapshot.conn.get_atom_name(d.target).unwrap().reply().unwrap();
+                    (
+                        String::from_utf8(name.name).unwrap(),
+                        String::from_utf8(d.value.clone()).unwrap(),
+                    )
+                })
+                .collect()
+        }
+
+        #[test]
+        fn oversized_formats_give_up_the_snapshot() {
+            let data = |format, len| Data {
+                target: 1,
+                kind: 1,
+                format,
+                value: vec![0; len],
+            };
+            assert_eq!(servable(&data(8, 100), 100), Ok(true));
+            assert_eq!(servable(&data(32, 0), 100), Ok(true));
+            assert_eq!(servable(&data(7, 10), 100), Ok(false));
+            assert!(servable(&data(8, 101), 100).is_err());
+        }
+
+        #[test]
+        #[ignore = "needs an X server, e.g. xvfb-run cargo test -- --ignored"]
+        fn every_format_survives_a_paste() {
+            own(&[
+                (b"UTF8_STRING", "plain"),
+                (b"text/html", "<b>rich</b>"),
+                (b"application/x-light-whisper-test", "custom"),
+            ])
+            .restore()
+            .unwrap();
+
+            let snapshot = Snapshot::take().unwrap().unwrap();
+            let expected = formats(&snapshot);
+            assert_eq!(expected.len(), 3);
+            assert!(expected.contains(&("text/html".to_string(), "<b>rich</b>".to_string())));
+
+            // The paste takes the clipboard over, then the snapshot is put back
+            TextOffer::new("transcript")
+                .unwrap()
+                .paste(&|| Ok(()))
+                .unwrap();
+            snapshot.restore().unwrap();
+
+            let again = Snapshot::take().unwrap().unwrap();
+            assert_eq!(formats(&again), expected);
+        }
+    }
+}
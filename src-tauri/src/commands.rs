@@ -1,89 +1,231 @@
-use tauri::AppHandle;
+use std::sync::atomic::Ordering;
+use tauri::{AppHandle, Emitter};
 
 use crate::audio;
 use crate::config;
+use crate::dictionary;
+use crate::history;
 use crate::model_manager;
-use crate::state::{get_model_path_for_config, AppState};
-use crate::stt;
+use crate::postprocess;
+use crate::recording;
+use crate::shortcuts;
+use crate::snippets;
+use crate::state::{self, apply_config_change, AppState};
+use crate::wayland;
 
 #[tauri::command]
 pub fn get_config(state: tauri::State<'_, AppState>) -> config::AppConfig {
     state.config.lock().unwrap().clone()
 }
 
+/// Validate and persist a settings object sent by the frontend. Rejected
+/// values are reported per field and nothing is saved.
 #[tauri::command]
 pub fn save_config(
-    config: config::AppConfig,
+    config: serde_json::Value,
+    app: AppHandle,
     state: tauri::State<'_, AppState>,
-) -> Result<(), String> {
-    config::save_config_to_disk(&config)?;
-
-    let old_config = state.config.lock().unwrap().clone();
-    *state.config.lock().unwrap() = config.clone();
-
-    // Reload engine if engine type or model size changed
-    let engine_changed = old_config.engine != config.engine;
-    let model_changed = old_config.model_size != config.model_size;
-
-    if engine_changed {
-        let mut engine = state.engine.lock().unwrap();
-        *engine = stt::SttEngine::from_engine_name(&config.engine);
-        // Try to load model for the new engine
-        let model_path = get_model_path_for_config(&config);
-        if model_path.exists() {
-            let _ = engine.load_model(&model_path);
-        }
-    } else if model_changed && config.engine == "whisper" {
-        let model_path = model_manager::whisper_model_path(&config.model_size);
-        if model_path.exists() {
-            let mut engine = state.engine.lock().unwrap();
-            let _ = engine.load_model(&model_path);
-        }
+) -> Result<(), config::ConfigError> {
+    let (config, errors) = config::parse_config(&config);
+    if !errors.is_empty() {
+        return Err(config::ConfigError::invalid(errors));
     }
+    // Reported with the details the shortcut editor needs
+    shortcuts::check(&config.bindings)?;
+    config.validate().map_err(config::ConfigError::invalid)?;
+
+    // Write while holding the lock so the config watcher, which compares the
+    // file against the in-memory config, never sees a half-applied save
+    let old_config = {
+        let mut current = state.config.lock().unwrap();
+        // Shortcuts the system refuses must never reach the disk
+        shortcuts::rebind(&app, &current.bindings, &config.bindings)?;
+        if let Err(e) = config::save_config_to_disk(&config) {
+            let _ = shortcuts::rebind(&app, &config.bindings, &current.bindings);
+            return Err(e.into());
+        }
+        std::mem::replace(&mut *current, config.clone())
+    };
 
+    apply_config_change(&app, &old_config, &config);
     Ok(())
 }
 
+/// Activate a profile by name and hot-reload the engine it needs.
+#[tauri::command]
+pub fn switch_profile(name: String, app: AppHandle) -> Result<(), String> {
+    state::switch_profile(&app, &name)
+}
+
+/// Run `text` through a post-processing chain and the dictionary, so the
+/// settings window can show the effect of a chain before it is saved.
+#[tauri::command]
+pub fn preview_postprocess(
+    text: String,
+    transforms: Vec<postprocess::TransformConfig>,
+    language: config::Language,
+    state: tauri::State<'_, AppState>,
+) -> Result<String, String> {
+    let profile = state.config.lock().unwrap().active_profile.clone();
+    let ctx = postprocess::Context {
+        language: &language,
+        profile: &profile,
+    };
+    // Network steps would run on every keystroke; the preview leaves them out
+    let transforms: Vec<_> = transforms
+        .into_iter()
+        .filter(|t| !matches!(t.transform, postprocess::Transform::LlmEdit(_)))
+        .collect();
+    let text = postprocess::run(&transforms, &text, &ctx);
+    Ok(dictionary::apply(&dictionary::load()?, &text, &ctx))
+}
+
+#[tauri::command]
+pub fn list_dictionary() -> Result<Vec<dictionary::DictionaryRule>, String> {
+    dictionary::load()
+}
+
+#[tauri::command]
+pub fn add_dictionary_rule(
+    rule: dictionary::DictionaryRule,
+) -> Result<dictionary::DictionaryRule, String> {
+    dictionary::add(rule)
+}
+
+#[tauri::command]
+pub fn update_dictionary_rule(rule: dictionary::DictionaryRule) -> Result<(), String> {
+    dictionary::update(rule)
+}
+
+#[tauri::command]
+pub fn delete_dictionary_rule(id: u64) -> Result<(), String> {
+    dictionary::delete(id)
+}
+
+/// Import rules from a `.csv` or `.json` file, appending to the dictionary
+/// or replacing it. Returns the number of rules imported.
+#[tauri::command]
+pub fn import_dictionary(path: String, replace: bool) -> Result<usize, String> {
+    dictionary::import(&config::expand_home(&path), replace)
+}
+
+/// Export the dictionary to a `.csv` or `.json` file. Returns the number of
+/// rules exported.
+#[tauri::command]
+pub fn export_dictionary(path: String) -> Result<usize, String> {
+    dictionary::export(&config::expand_home(&path))
+}
+
+/// Remove the last pasted dictation from the app it went to.
+#[tauri::command]
+pub fn undo_last_dictation(app: AppHandle) -> Result<(), String> {
+    recording::undo_last(&app)
+}
+
+#[tauri::command]
+pub fn list_snippets() -> Result<Vec<snippets::Snippet>, String> {
+    snippets::load()
+}
+
+#[tauri::command]
+pub fn add_snippet(snippet: snippets::Snippet) -> Result<snippets::Snippet, String> {
+    snippets::add(snippet)
+}
+
+#[tauri::command]
+pub fn update_snippet(snippet: snippets::Snippet) -> Result<(), String> {
+    snippets::update(snippet)
+}
+
+#[tauri::command]
+pub fn delete_snippet(id: u64) -> Result<(), String> {
+    snippets::delete(id)
+}
+
+/// Whether an engine is currently loaded, and whether a load is in progress.
+#[tauri::command]
+pub fn get_engine_status(state: tauri::State<'_, AppState>) -> serde_json::Value {
+    serde_json::json!({
+        "loading": state.engine_loading.load(Ordering::SeqCst),
+        "loaded": state.engine.try_lock().map(|e| e.is_loaded()).unwrap_or(true),
+    })
+}
+
+/// Problems found at startup, handed out once.
+#[tauri::command]
+pub fn take_startup_notices(state: tauri::State<'_, AppState>) -> Vec<String> {
+    std::mem::take(&mut *state.startup_notices.lock().unwrap())
+}
+
+/// What works and what is missing for pasting in a Wayland session.
+#[tauri::command]
+pub fn wayland_diagnostics() -> wayland::Diagnostics {
+    wayland::diagnose()
+}
+
+/// Most recent transcriptions first.
+#[tauri::command]
+pub fn get_history(limit: usize) -> Vec<history::HistoryEntry> {
+    history::load(limit)
+}
+
 #[tauri::command]
 pub fn list_audio_devices() -> Vec<String> {
     audio::list_input_devices()
 }
 
 #[tauri::command]
-pub fn check_model_exists(engine: String, model_size: String) -> bool {
-    model_manager::model_exists_for_engine(&engine, &model_size)
+pub fn check_model_exists(engine: config::Engine, model_size: config::ModelSize) -> bool {
+    model_manager::model_exists_for_engine(engine, model_size)
 }
 
 #[tauri::command]
-pub async fn download_model(engine: String, model_size: String, app: AppHandle) -> Result<(), String> {
-    match engine.as_str() {
-        "parakeet" => {
+pub async fn download_model(
+    engine: config::Engine,
+    model_size: config::ModelSize,
+    app: AppHandle,
+) -> Result<(), String> {
+    match engine {
+        config::Engine::Parakeet => {
             model_manager::download_parakeet_model(app).await?;
         }
-        _ => {
-            model_manager::download_whisper_model(&model_size, app).await?;
+        config::Engine::Whisper => {
+            model_manager::download_whisper_model(model_size, app).await?;
+        }
+        config::Engine::Remote => {
+            return Err("The remote engine has no model to download".to_string());
         }
     }
     Ok(())
 }
 
+/// Replace all global shortcut bindings, keeping the rest of the config.
+/// Either every binding takes effect or none does.
 #[tauri::command]
-pub fn change_shortcut(shortcut: String, app: AppHandle, state: tauri::State<'_, AppState>) -> Result<(), String> {
-    use tauri_plugin_global_shortcut::GlobalShortcutExt;
-
-    // Unregister all shortcuts then register the new one
-    app.global_shortcut()
-        .unregister_all()
-        .map_err(|e| format!("Failed to unregister shortcuts: {}", e))?;
-
-    app.global_shortcut()
-        .register(shortcut.as_str())
-        .map_err(|e| format!("Invalid shortcut '{}': {}", shortcut, e))?;
+pub fn set_bindings(
+    bindings: Vec<config::KeyBinding>,
+    app: AppHandle,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), shortcuts::ShortcutError> {
+    {
+        let mut current = state.config.lock().unwrap();
+        let mut config = current.clone();
+        config.bindings = bindings;
+        if let Err(errors) = config.validate() {
+            // Shortcut problems are reported by `rebind` below in more detail
+            if let Some(e) = errors.iter().find(|e| !e.field.ends_with(".shortcut")) {
+                return Err(shortcuts::ShortcutError::invalid_binding(&e.field, &e.message));
+            }
+        }
 
-    // Update config in memory and on disk
-    let mut cfg = state.config.lock().unwrap();
-    cfg.shortcut = shortcut;
-    config::save_config_to_disk(&cfg).map_err(|e| format!("Failed to save config: {}", e))?;
+        shortcuts::rebind(&app, &current.bindings, &config.bindings)?;
+        if let Err(e) = config::save_config_to_disk(&config) {
+            let _ = shortcuts::rebind(&app, &config.bindings, &current.bindings);
+            return Err(shortcuts::ShortcutError::save(e));
+        }
+        *current = config;
+    }
 
+    let _ = app.emit("config-changed", ());
     Ok(())
 }
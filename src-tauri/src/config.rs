@@ -1,70 +1,1490 @@
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::focus::AppIdentity;
+use crate::hallucination::HallucinationFilter;
+use crate::output::{self, Output};
+use crate::postprocess::TransformConfig;
+use crate::shortcuts::ShortcutError;
+use crate::voice::{CommandAction, VoiceCommands};
+
+/// Current `config.json` schema version; bump it and append to [`MIGRATIONS`]
+/// whenever the on-disk layout changes.
+pub const CONFIG_VERSION: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Engine {
+    Whisper,
+    Parakeet,
+    /// An OpenAI-compatible transcription server, see [`RemoteServer`]
+    Remote,
+}
+
+impl Engine {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Engine::Whisper => "whisper",
+            Engine::Parakeet => "parakeet",
+            Engine::Remote => "remote",
+        }
+    }
+}
+
+impl fmt::Display for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Whisper model size. Ignored by Parakeet, which ships a single model, and
+/// by the remote engine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModelSize {
+    Tiny,
+    Base,
+    Small,
+    Medium,
+}
+
+impl ModelSize {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModelSize::Tiny => "tiny",
+            ModelSize::Base => "base",
+            ModelSize::Small => "small",
+            ModelSize::Medium => "medium",
+        }
+    }
+}
+
+impl fmt::Display for ModelSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Language codes understood by whisper.cpp
+const WHISPER_LANGUAGES: &[&str] = &[
+    "en", "zh", "de", "es", "ru", "ko", "fr", "ja", "pt", "tr", "pl", "ca", "nl", "ar", "sv",
+    "it", "id", "hi", "fi", "vi", "he", "uk", "el", "ms", "cs", "ro", "da", "hu", "ta", "no",
+    "th", "ur", "hr", "bg", "lt", "la", "mi", "ml", "cy", "sk", "te", "fa", "lv", "bn", "sr",
+    "az", "sl", "kn", "et", "mk", "br", "eu", "is", "hy", "ne", "mn", "bs", "kk", "sq", "sw",
+    "gl", "mr", "pa", "si", "km", "sn", "yo", "so", "af", "oc", "ka", "be", "tg", "sd", "gu",
+    "am", "yi", "lo", "uz", "fo", "ht", "ps", "tk", "nn", "mt", "sa", "lb", "my", "bo", "tl",
+    "mg", "as", "tt", "haw", "ln", "ha", "ba", "jw", "su", "yue",
+];
+
+/// Transcription language: `"auto"` or a code from [`WHISPER_LANGUAGES`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Language(String);
+
+impl Language {
+    pub fn auto() -> Self {
+        Self("auto".to_string())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The language code to force, or `None` for auto-detection.
+    pub fn code(&self) -> Option<&str> {
+        if self.0 == "auto" {
+            None
+        } else {
+            Some(&self.0)
+        }
+    }
+}
+
+impl TryFrom<String> for Language {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if value == "auto" || WHISPER_LANGUAGES.contains(&value.as_str()) {
+            Ok(Self(value))
+        } else {
+            Err(format!("unknown language `{}`, expected `auto` or a language code such as `en` or `fr`", value))
+        }
+    }
+}
+
+impl From<Language> for String {
+    fn from(language: Language) -> Self {
+        language.0
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A named set of dictation settings, e.g. "French meetings" or
+/// "English code comments". Exactly one profile is active at a time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(default)]
-pub struct AppConfig {
+pub struct Profile {
+    pub name: String,
+    pub engine: Engine,
+    pub model_size: ModelSize,
+    pub language: Language,
     pub audio_device: String,
-    pub model_size: String,
-    pub language: String,
-    pub engine: String,
+    /// Initial prompt biasing Whisper or the remote server towards a
+    /// vocabulary or style. Ignored by Parakeet.
+    pub prompt: String,
+    /// Drops text Whisper makes up on silence. Ignored by Parakeet and the
+    /// remote engine.
+    pub hallucination_filter: HallucinationFilter,
+    /// Transforms applied to every transcription, in order
+    pub postprocess: Vec<TransformConfig>,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            name: "Default".to_string(),
+            engine: Engine::Whisper,
+            model_size: ModelSize::Base,
+            language: Language::auto(),
+            audio_device: "default".to_string(),
+            prompt: String::new(),
+            hallucination_filter: HallucinationFilter::default(),
+            postprocess: Vec::new(),
+        }
+    }
+}
+
+/// What a global shortcut does when pressed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Action {
+    /// Start recording, or stop and transcribe
+    Toggle,
+    /// Record while the shortcut is held down
+    PushToTalk,
+    /// Discard the current recording. Only grabbed while recording.
+    Cancel,
+    /// Paste the last transcription again
+    RepasteLast,
+    /// Like `Toggle`, but Whisper translates the dictation to English
+    Translate,
+    SwitchProfile {
+        profile: String,
+    },
+    /// Like `Toggle`, but forces a language for this dictation
+    DictateLanguage {
+        language: Language,
+    },
+    /// Like `Toggle`, but the dictation is a voice command to run
+    Command,
+    /// Remove the last pasted dictation from the app it went to
+    UndoLast,
+}
+
+impl Action {
+    /// Human-readable description, for error messages.
+    pub fn label(&self) -> String {
+        match self {
+            Action::Toggle => "start/stop dictation".to_string(),
+            Action::PushToTalk => "push to talk".to_string(),
+            Action::Cancel => "cancel recording".to_string(),
+            Action::RepasteLast => "paste last dictation again".to_string(),
+            Action::Translate => "dictate and translate".to_string(),
+            Action::SwitchProfile { profile } => format!("switch to profile `{}`", profile),
+            Action::DictateLanguage { language } => format!("dictate in `{}`", language),
+            Action::Command => "voice command".to_string(),
+            Action::UndoLast => "undo last dictation".to_string(),
+        }
+    }
+}
+
+/// A global shortcut (e.g. `"Alt+Space"`) and the action it triggers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyBinding {
     pub shortcut: String,
+    #[serde(flatten)]
+    pub action: Action,
+}
+
+impl KeyBinding {
+    pub fn new(shortcut: &str, action: Action) -> Self {
+        Self {
+            shortcut: shortcut.to_string(),
+            action,
+        }
+    }
+}
+
+/// How the last dictation is undone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum UndoMethod {
+    /// One Backspace per pasted character
+    #[default]
+    Backspace,
+    /// The app's undo shortcut, Cmd+Z or Ctrl+Z. Better for apps that
+    /// auto-indent or auto-close brackets, but may undo more than the paste.
+    UndoChord,
+}
+
+/// Server used by the `remote` engine.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RemoteServer {
+    /// Full URL of an OpenAI-compatible transcription endpoint, e.g.
+    /// `http://localhost:8000/v1/audio/transcriptions` (faster-whisper-server)
+    pub url: String,
+    /// Model name sent with the request
+    pub model: String,
+    /// Sent as a bearer token when not empty
+    pub api_key: String,
+    /// Give up on a request after this long
+    pub timeout_secs: u64,
+}
+
+impl Default for RemoteServer {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            model: "whisper-1".to_string(),
+            api_key: String::new(),
+            timeout_secs: 30,
+        }
+    }
+}
+
+/// What is kept in `history.jsonl`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistorySettings {
+    /// Record dictations at all
+    pub enabled: bool,
+    /// The oldest entries are dropped beyond this many; 0 keeps everything
+    pub max_entries: usize,
+}
+
+impl Default for HistorySettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_entries: 1000,
+        }
+    }
+}
+
+/// Overrides for dictations into one app, e.g. a terminal that pastes with
+/// Ctrl+Shift+V or a chat app where sentences need no final period.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct AppRule {
+    /// Process name, X11 `WM_CLASS` or macOS bundle id of the app, compared
+    /// case-insensitively, e.g. `kitty` or `com.tinyspeck.slackmacgap`
+    pub app: String,
+    /// Shortcut the paste output presses instead of Cmd+V / Ctrl+V
+    pub paste_chord: Option<String>,
+    /// Type the text where the paste output would paste it, for apps that
+    /// block paste
+    pub type_instead_of_paste: bool,
+    /// Post-process with the transforms of this profile instead of the
+    /// active one's
+    pub postprocess_profile: Option<String>,
+    /// Drop one trailing period after post-processing
+    pub strip_trailing_period: bool,
+    /// Undo dictations into this app this way instead of with
+    /// [`AppConfig::undo_method`], e.g. Backspaces in a terminal where
+    /// Ctrl+Z suspends the running program
+    pub undo_method: Option<UndoMethod>,
+}
+
+impl AppRule {
+    pub fn matches(&self, app: &AppIdentity) -> bool {
+        let name = self.app.trim();
+        app.names().any(|n| n.eq_ignore_ascii_case(name))
+    }
+}
+
+pub fn default_bindings() -> Vec<KeyBinding> {
+    vec![
+        KeyBinding::new("Alt+Space", Action::Toggle),
+        KeyBinding::new("Escape", Action::Cancel),
+    ]
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    pub version: u32,
+    pub profiles: Vec<Profile>,
+    /// Name of the profile in use
+    pub active_profile: String,
+    pub bindings: Vec<KeyBinding>,
+    /// Phrases that run an action instead of being pasted
+    pub voice_commands: VoiceCommands,
+    pub undo_method: UndoMethod,
+    /// Where dictations go, all of them in turn
+    pub outputs: Vec<Output>,
+    /// Secondary engine re-run on the same audio when the primary fails or
+    /// returns nothing. `None` disables the fallback.
+    pub fallback_engine: Option<Engine>,
+    pub fallback_model_size: ModelSize,
+    /// Load the fallback at startup instead of on first use
+    pub fallback_eager: bool,
+    /// Server used by profiles with the `remote` engine
+    pub remote: RemoteServer,
+    /// Per-app overrides; the first rule matching the app dictated into
+    /// applies
+    pub app_rules: Vec<AppRule>,
+    pub history: HistorySettings,
 }
 
 impl Default for AppConfig {
     fn default() -> Self {
+        let profile = Profile::default();
         Self {
-            audio_device: "default".to_string(),
-            model_size: "base".to_string(),
-            language: "auto".to_string(),
-            engine: "whisper".to_string(),
-            shortcut: "Alt+Space".to_string(),
+            version: CONFIG_VERSION,
+            active_profile: profile.name.clone(),
+            profiles: vec![profile],
+            bindings: default_bindings(),
+            voice_commands: VoiceCommands::default(),
+            undo_method: UndoMethod::default(),
+            outputs: output::default_outputs(),
+            fallback_engine: None,
+            fallback_model_size: ModelSize::Base,
+            fallback_eager: false,
+            remote: RemoteServer::default(),
+            app_rules: Vec::new(),
+            history: HistorySettings::default(),
+        }
+    }
+}
+
+impl AppConfig {
+    /// The active profile. Falls back to the first one if `active_profile`
+    /// names a profile that does not exist, which [`validate`](Self::validate)
+    /// rejects for saved configs.
+    pub fn active(&self) -> &Profile {
+        self.profile(&self.active_profile)
+            .or(self.profiles.first())
+            .expect("config has at least one profile")
+    }
+
+    pub fn profile(&self, name: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|p| p.name == name)
+    }
+
+    /// The rule for dictations into `app`, if any.
+    pub fn app_rule(&self, app: &AppIdentity) -> Option<&AppRule> {
+        self.app_rules.iter().find(|r| r.matches(app))
+    }
+
+    /// Checks that go beyond what deserialization already enforces.
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+
+        if self.profiles.is_empty() {
+            errors.push(FieldError::new(
+                "profiles",
+                "at least one profile is required",
+            ));
+        }
+
+        for (i, profile) in self.profiles.iter().enumerate() {
+            let field = |name: &str| format!("profiles[{}].{}", i, name);
+            if profile.name.trim().is_empty() {
+                errors.push(FieldError::new(&field("name"), "must not be empty"));
+            } else if self.profiles[..i].iter().any(|p| p.name == profile.name) {
+                errors.push(FieldError::new(
+                    &field("name"),
+                    &format!("duplicate profile name `{}`", profile.name),
+                ));
+            }
+            if profile.audio_device.trim().is_empty() {
+                errors.push(FieldError::new(
+                    &field("audio_device"),
+                    "must not be empty (use `default`)",
+                ));
+            }
+        }
+
+        match self.profile(&self.active_profile) {
+            None => errors.push(FieldError::new(
+                "active_profile",
+                &format!("no profile named `{}`", self.active_profile),
+            )),
+            Some(active) => {
+                if let Some(fallback) = self.fallback_engine {
+                    // Only Whisper has model sizes to tell two engines apart
+                    let same_model = fallback != Engine::Whisper
+                        || self.fallback_model_size == active.model_size;
+                    if fallback == active.engine && same_model {
+                        errors.push(FieldError::new(
+                            "fallback_engine",
+                            "must differ from the primary engine and model",
+                        ));
+                    }
+                }
+            }
+        }
+
+        if let Err(e) = crate::shortcuts::check(&self.bindings) {
+            let field = match e.index {
+                Some(i) => format!("bindings[{}].shortcut", i),
+                None => "bindings".to_string(),
+            };
+            errors.push(FieldError::new(&field, &e.message));
+        }
+        for (i, binding) in self.bindings.iter().enumerate() {
+            if let Action::SwitchProfile { profile } = &binding.action {
+                if self.profile(profile).is_none() {
+                    errors.push(FieldError::new(
+                        &format!("bindings[{}].profile", i),
+                        &format!("no profile named `{}`", profile),
+                    ));
+                }
+            }
+        }
+
+        for (i, command) in self.voice_commands.commands.iter().enumerate() {
+            let field = |name: &str| format!("voice_commands.commands[{}].{}", i, name);
+            let phrase = crate::text::normalize(&command.phrase);
+            if phrase.is_empty() {
+                errors.push(FieldError::new(&field("phrase"), "must not be empty"));
+            } else if self.voice_commands.commands[..i]
+                .iter()
+                .any(|c| crate::text::normalize(&c.phrase) == phrase)
+            {
+                errors.push(FieldError::new(
+                    &field("phrase"),
+                    &format!("duplicate command `{}`", command.phrase),
+                ));
+            }
+            match &command.action {
+                CommandAction::Keys { keys } => {
+                    if let Err(e) = crate::paste::check_chord(keys) {
+                        errors.push(FieldError::new(&field("keys"), &e));
+                    }
+                }
+                CommandAction::SwitchProfile { profile } => {
+                    if self.profile(profile).is_none() {
+                        errors.push(FieldError::new(
+                            &field("profile"),
+                            &format!("no profile named `{}`", profile),
+                        ));
+                    }
+                }
+                CommandAction::Shell { command } => {
+                    if command.trim().is_empty() {
+                        errors.push(FieldError::new(&field("command"), "must not be empty"));
+                    }
+                }
+                CommandAction::UndoLast => {}
+            }
+        }
+
+        if self.outputs.is_empty() {
+            errors.push(FieldError::new("outputs", "at least one output is required"));
+        }
+        if self.outputs.contains(&Output::Paste) && self.outputs.contains(&Output::Type) {
+            errors.push(FieldError::new(
+                "outputs",
+                "paste and type would both insert the text; choose one",
+            ));
+        }
+        for (i, output) in self.outputs.iter().enumerate() {
+            if let Output::File { path } | Output::Fifo { path } = output {
+                if path.trim().is_empty() {
+                    errors.push(FieldError::new(
+                        &format!("outputs[{}].path", i),
+                        "must not be empty",
+                    ));
+                }
+            }
+        }
+
+        let uses_remote = self.profiles.iter().any(|p| p.engine == Engine::Remote)
+            || self.fallback_engine == Some(Engine::Remote);
+        if uses_remote {
+            let url = self.remote.url.trim();
+            if url.is_empty() {
+                errors.push(FieldError::new(
+                    "remote.url",
+                    "must not be empty when a profile uses the remote engine",
+                ));
+            } else if !url.starts_with("http://") && !url.starts_with("https://") {
+                errors.push(FieldError::new(
+                    "remote.url",
+                    "must start with http:// or https://",
+                ));
+            }
+        }
+        if self.remote.timeout_secs == 0 {
+            errors.push(FieldError::new("remote.timeout_secs", "must be at least 1"));
+        }
+
+        for (i, rule) in self.app_rules.iter().enumerate() {
+            let field = |name: &str| format!("app_rules[{}].{}", i, name);
+            let app = rule.app.trim();
+            if app.is_empty() {
+                errors.push(FieldError::new(&field("app"), "must not be empty"));
+            } else if self.app_rules[..i]
+                .iter()
+                .any(|r| r.app.trim().eq_ignore_ascii_case(app))
+            {
+                errors.push(FieldError::new(
+                    &field("app"),
+                    &format!("duplicate rule for `{}`", rule.app),
+                ));
+            }
+            if let Some(chord) = &rule.paste_chord {
+                if let Err(e) = crate::paste::check_chord(chord) {
+                    errors.push(FieldError::new(&field("paste_chord"), &e));
+                }
+            }
+            if let Some(profile) = &rule.postprocess_profile {
+                if self.profile(profile).is_none() {
+                    errors.push(FieldError::new(
+                        &field("postprocess_profile"),
+                        &format!("no profile named `{}`", profile),
+                    ));
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// A rejected config value, keyed by its field name in `config.json`.
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, message: &str) -> Self {
+        Self {
+            field: field.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Error returned by config commands. `fields` is empty when the failure is
+/// not tied to a particular setting (e.g. the file could not be written).
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigError {
+    pub message: String,
+    pub fields: Vec<FieldError>,
+    /// Set when the shortcuts were rejected, with the details the shortcut
+    /// editor shows
+    pub shortcut: Option<ShortcutError>,
+}
+
+impl ConfigError {
+    pub fn invalid(fields: Vec<FieldError>) -> Self {
+        let names: Vec<&str> = fields.iter().map(|f| f.field.as_str()).collect();
+        Self {
+            message: format!("Invalid settings: {}", names.join(", ")),
+            fields,
+            shortcut: None,
+        }
+    }
+}
+
+impl From<String> for ConfigError {
+    fn from(message: String) -> Self {
+        Self {
+            message,
+            fields: Vec::new(),
+            shortcut: None,
+        }
+    }
+}
+
+impl From<ShortcutError> for ConfigError {
+    fn from(error: ShortcutError) -> Self {
+        let field = match error.index {
+            Some(i) => format!("bindings[{}].shortcut", i),
+            None => "bindings".to_string(),
+        };
+        Self {
+            message: error.message.clone(),
+            fields: vec![FieldError::new(&field, &error.message)],
+            shortcut: Some(error),
+        }
+    }
+}
+
+/// `field (message); field (message)` — for notices and logs.
+fn describe_field_errors(errors: &[FieldError]) -> String {
+    let fields: Vec<String> = errors
+        .iter()
+        .map(|e| format!("{} ({})", e.field, e.message))
+        .collect();
+    fields.join("; ")
+}
+
+/// Deserialize a config object one field at a time, so that a bad value only
+/// costs that field (which keeps its default) and is reported by name.
+pub fn parse_config(value: &serde_json::Value) -> (AppConfig, Vec<FieldError>) {
+    let Some(fields) = value.as_object() else {
+        return (
+            AppConfig::default(),
+            vec![FieldError::new("", "expected a JSON object")],
+        );
+    };
+
+    let mut merged =
+        serde_json::to_value(AppConfig::default()).expect("default config is serializable");
+    let mut errors = Vec::new();
+
+    for (key, field_value) in fields {
+        let mut candidate = merged.clone();
+        candidate[key] = field_value.clone();
+        match serde_json::from_value::<AppConfig>(candidate) {
+            // Everything else leans on there always being an active profile
+            Ok(c) if c.profiles.is_empty() => {
+                errors.push(FieldError::new(key, "at least one profile is required"))
+            }
+            Ok(_) => merged[key] = field_value.clone(),
+            Err(e) => errors.push(FieldError::new(key, &e.to_string())),
+        }
+    }
+
+    let config = serde_json::from_value(merged).unwrap_or_default();
+    (config, errors)
+}
+
+// ── Directories ──
+//
+// On Linux, settings, data (models, history) and state (logs) follow the XDG
+// base directory spec. Elsewhere everything lives in `~/lightwhisper`, as it
+// always did. `LIGHTWHISPER_HOME` puts everything in one directory of the
+// caller's choosing on every platform.
+
+/// Environment variable overriding every directory below
+pub const HOME_ENV: &str = "LIGHTWHISPER_HOME";
+
+const APP_DIR_NAME: &str = "lightwhisper";
+
+fn home_override() -> Option<PathBuf> {
+    std::env::var_os(HOME_ENV)
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+}
+
+/// The pre-XDG location, also still the default outside Linux.
+fn legacy_dir() -> PathBuf {
+    match dirs::home_dir() {
+        Some(home) => home.join(APP_DIR_NAME),
+        None => {
+            eprintln!("Cannot find home directory, using a temporary directory");
+            std::env::temp_dir().join(APP_DIR_NAME)
         }
     }
 }
 
+/// `<xdg base>/lightwhisper` on Linux, the legacy directory elsewhere.
+fn app_dir(xdg_base: fn() -> Option<PathBuf>) -> PathBuf {
+    app_dir_in(home_override(), xdg_base)
+}
+
+/// [`app_dir`] with the `LIGHTWHISPER_HOME` value passed in.
+fn app_dir_in(home: Option<PathBuf>, xdg_base: fn() -> Option<PathBuf>) -> PathBuf {
+    if let Some(dir) = home {
+        return dir;
+    }
+    if cfg!(target_os = "linux") {
+        if let Some(base) = xdg_base() {
+            return base.join(APP_DIR_NAME);
+        }
+    }
+    legacy_dir()
+}
+
+/// Settings: `config.json` and its backups.
 pub fn config_dir() -> PathBuf {
-    let home = dirs::home_dir().expect("Cannot find home directory");
-    home.join("lightwhisper")
+    app_dir(dirs::config_dir)
+}
+
+/// Large or accumulating data: models and history.
+pub fn data_dir() -> PathBuf {
+    app_dir(dirs::data_dir)
+}
+
+/// Logs.
+pub fn state_dir() -> PathBuf {
+    app_dir(dirs::state_dir)
 }
 
 pub fn config_path() -> PathBuf {
     config_dir().join("config.json")
 }
 
+/// Resolve a leading `~/` in a path typed in the settings window.
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
 pub fn models_dir() -> PathBuf {
-    config_dir().join("models")
+    data_dir().join("models")
 }
 
 pub fn parakeet_models_dir() -> PathBuf {
     models_dir().join("parakeet-tdt")
 }
 
-pub fn load_config() -> AppConfig {
-    let path = config_path();
-    if path.exists() {
-        match fs::read_to_string(&path) {
-            Ok(content) => serde_json::from_str(&content).unwrap_or_default(),
-            Err(_) => AppConfig::default(),
+/// Move an existing `~/lightwhisper` into the XDG directories.
+fn migrate_legacy_dir() {
+    let legacy = legacy_dir();
+    if home_override().is_some() || !legacy.is_dir() || legacy == config_dir() {
+        return;
+    }
+    migrate_dir(&legacy, &config_dir(), &data_dir());
+}
+
+/// Move the entries of `legacy` into `config_dir` (`config.json` and its
+/// backups) or `data_dir` (everything else). Entries that already exist at
+/// the destination are left alone, and `legacy` is only removed once it is
+/// empty.
+fn migrate_dir(legacy: &Path, config_dir: &Path, data_dir: &Path) {
+    let entries = match fs::read_dir(legacy) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("Cannot read {}: {}", legacy.display(), e);
+            return;
+        }
+    };
+
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let is_config = name.to_string_lossy().starts_with("config.json");
+        let dest_dir = if is_config { config_dir } else { data_dir };
+        let dest = dest_dir.join(&name);
+        if dest.exists() {
+            continue;
+        }
+
+        let _ = fs::create_dir_all(dest_dir);
+        match move_path(&entry.path(), &dest) {
+            Ok(()) => eprintln!("Moved {} to {}", entry.path().display(), dest.display()),
+            Err(e) => eprintln!("Failed to move {}: {}", entry.path().display(), e),
+        }
+    }
+
+    // Fails (harmlessly) if anything was left behind
+    let _ = fs::remove_dir(legacy);
+}
+
+/// Rename, falling back to copy + delete across filesystems.
+fn move_path(from: &Path, to: &Path) -> std::io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    copy_into_place(from, to)?;
+    remove_path(from)
+}
+
+/// Copy `from` to `<to>.tmp`, then rename it to `to` once complete, so a
+/// copy cut short (e.g. by a full disk) never passes for a finished one and
+/// is redone on the next start.
+fn copy_into_place(from: &Path, to: &Path) -> std::io::Result<()> {
+    let mut tmp_name = to.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp = to.with_file_name(tmp_name);
+    // Left over from an interrupted copy
+    remove_path(&tmp)?;
+
+    if let Err(e) = copy_recursive(from, &tmp) {
+        let _ = remove_path(&tmp);
+        return Err(e);
+    }
+    fs::rename(&tmp, to)
+}
+
+/// Remove a file or directory tree; nothing to do if it does not exist.
+fn remove_path(path: &Path) -> std::io::Result<()> {
+    let result = if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    };
+    match result {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        other => other,
+    }
+}
+
+fn copy_recursive(from: &Path, to: &Path) -> std::io::Result<()> {
+    if from.is_dir() {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
         }
+        Ok(())
     } else {
-        AppConfig::default()
+        fs::copy(from, to).map(|_| ())
     }
 }
 
+/// Outcome of reading `config.json` at startup.
+pub struct LoadedConfig {
+    pub config: AppConfig,
+    /// Set when the file had to be repaired; meant to be shown to the user
+    pub recovered: Option<String>,
+}
+
+pub fn load_config() -> LoadedConfig {
+    load_config_from(&config_path())
+}
+
+fn load_config_from(path: &Path) -> LoadedConfig {
+    if !path.exists() {
+        return LoadedConfig {
+            config: AppConfig::default(),
+            recovered: None,
+        };
+    }
+
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            return LoadedConfig {
+                config: AppConfig::default(),
+                recovered: Some(format!(
+                    "Could not read {} ({}) — using default settings",
+                    path.display(),
+                    e
+                )),
+            };
+        }
+    };
+
+    let raw = serde_json::from_str::<serde_json::Value>(&content).map_err(|e| e.to_string());
+    let on_disk_version = raw
+        .as_ref()
+        .ok()
+        .and_then(|v| v.get("version"))
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+    let value = raw.and_then(migrate);
+
+    let value = match value {
+        Ok(value) => value,
+        Err(e) => {
+            let backup = backup_config(path);
+            return LoadedConfig {
+                config: AppConfig::default(),
+                recovered: Some(format!(
+                    "Your settings file could not be parsed ({}) and was reset to defaults. {}",
+                    e, backup
+                )),
+            };
+        }
+    };
+
+    let (config, errors) = parse_config(&value);
+    if !errors.is_empty() {
+        let backup = backup_config(path);
+        return LoadedConfig {
+            config,
+            recovered: Some(format!(
+                "Some settings were invalid and reset to defaults: {}. {}",
+                describe_field_errors(&errors),
+                backup
+            )),
+        };
+    }
+
+    // Saving rewrites the file in this version's layout and drops what a
+    // newer build added, so keep the original
+    if on_disk_version > CONFIG_VERSION as u64 {
+        let backup = backup_config(path);
+        return LoadedConfig {
+            config,
+            recovered: Some(format!(
+                "Your settings file was written by a newer version of Light Whisper; \
+                 settings this version does not know are dropped when it saves. {}",
+                backup
+            )),
+        };
+    }
+
+    // Persist the upgraded layout so migrations only ever run once
+    if on_disk_version < CONFIG_VERSION as u64 {
+        if let Err(e) = write_config(path, &config) {
+            eprintln!("Failed to write migrated config: {}", e);
+        }
+    }
+
+    LoadedConfig {
+        config,
+        recovered: None,
+    }
+}
+
+/// Copy `config.json` aside as `config.json.bak-<unix time>`. Returns a
+/// sentence describing where the copy went, for the user notice.
+fn backup_config(path: &Path) -> String {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".bak-{}", timestamp));
+    let backup = path.with_file_name(name);
+
+    match fs::copy(path, &backup) {
+        Ok(_) => format!("The original file was saved as {}", backup.display()),
+        Err(e) => {
+            eprintln!("Failed to back up config: {}", e);
+            "The original file could not be backed up".to_string()
+        }
+    }
+}
+
+// ── Schema migrations ──
+
+/// Bring a raw config object up to [`CONFIG_VERSION`], one step at a time.
+/// Files written before versioning existed count as version 0.
+fn migrate(mut value: serde_json::Value) -> Result<serde_json::Value, String> {
+    if !value.is_object() {
+        return Err("expected a JSON object".to_string());
+    }
+
+    let mut version = value.get("version").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+    if version > CONFIG_VERSION {
+        // Written by a newer build: keep what we understand, ignore the rest
+        eprintln!(
+            "config.json has version {}, newer than supported {}",
+            version, CONFIG_VERSION
+        );
+        return Ok(value);
+    }
+
+    while version < CONFIG_VERSION {
+        value = MIGRATIONS[version as usize](value);
+        version += 1;
+        value["version"] = version.into();
+    }
+
+    Ok(value)
+}
+
+/// `MIGRATIONS[n]` upgrades a version `n` object to version `n + 1`.
+const MIGRATIONS: &[fn(serde_json::Value) -> serde_json::Value] =
+    &[migrate_v0_to_v1, migrate_v1_to_v2, migrate_v2_to_v3];
+
+/// v0 stored every setting as a free-form string: an empty `fallback_engine`
+/// meant "none", and engine/model/language were matched case-sensitively.
+fn migrate_v0_to_v1(mut value: serde_json::Value) -> serde_json::Value {
+    if value.get("fallback_engine").and_then(|v| v.as_str()) == Some("") {
+        value["fallback_engine"] = serde_json::Value::Null;
+    }
+
+    for key in ["engine", "model_size", "fallback_model_size", "language"] {
+        if let Some(s) = value.get(key).and_then(|v| v.as_str()) {
+            value[key] = s.trim().to_lowercase().into();
+        }
+    }
+
+    value
+}
+
+/// v1 had a single flat set of engine/model/language/device settings; v2
+/// moves them into a "Default" profile.
+fn migrate_v1_to_v2(mut value: serde_json::Value) -> serde_json::Value {
+    let Some(obj) = value.as_object_mut() else {
+        return value;
+    };
+
+    let mut profile = serde_json::Map::new();
+    profile.insert("name".to_string(), "Default".into());
+    for key in ["engine", "model_size", "language", "audio_device"] {
+        if let Some(v) = obj.remove(key) {
+            profile.insert(key.to_string(), v);
+        }
+    }
+
+    obj.insert("profiles".to_string(), serde_json::json!([profile]));
+    obj.insert("active_profile".to_string(), "Default".into());
+    value
+}
+
+/// v2 had a single toggle `shortcut` and Escape hard-wired as cancel; v3
+/// binds any number of shortcuts to actions.
+fn migrate_v2_to_v3(mut value: serde_json::Value) -> serde_json::Value {
+    let Some(obj) = value.as_object_mut() else {
+        return value;
+    };
+
+    let shortcut = obj
+        .remove("shortcut")
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_else(|| "Alt+Space".to_string());
+    obj.insert(
+        "bindings".to_string(),
+        serde_json::json!([
+            { "shortcut": shortcut, "action": "toggle" },
+            { "shortcut": "Escape", "action": "cancel" },
+        ]),
+    );
+    value
+}
+
+/// Read `config.json` strictly: unlike [`load_config`], any problem is an
+/// error and nothing is backed up or reset. Used to pick up external edits.
+pub fn read_config_file() -> Result<AppConfig, String> {
+    let content =
+        fs::read_to_string(config_path()).map_err(|e| format!("Failed to read config: {}", e))?;
+    let value = serde_json::from_str::<serde_json::Value>(&content).map_err(|e| e.to_string())?;
+    // Applying it would mean saving it back in an older layout
+    if let Some(version) = value.get("version").and_then(|v| v.as_u64()) {
+        if version > CONFIG_VERSION as u64 {
+            return Err(format!(
+                "written by a newer version of Light Whisper (version {})",
+                version
+            ));
+        }
+    }
+    let value = migrate(value)?;
+
+    let (config, errors) = parse_config(&value);
+    if !errors.is_empty() {
+        return Err(describe_field_errors(&errors));
+    }
+    config
+        .validate()
+        .map_err(|errors| describe_field_errors(&errors))?;
+
+    Ok(config)
+}
+
+/// Write `config.json` atomically: the new content goes to a temp file in
+/// the same directory which is then renamed over the old one, so a crash
+/// mid-write never leaves a truncated config behind.
 pub fn save_config_to_disk(config: &AppConfig) -> Result<(), String> {
-    let dir = config_dir();
-    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create config dir: {}", e))?;
+    write_config(&config_path(), config)
+}
 
+fn write_config(path: &Path, config: &AppConfig) -> Result<(), String> {
     let json =
         serde_json::to_string_pretty(config).map_err(|e| format!("Failed to serialize: {}", e))?;
-    fs::write(config_path(), json).map_err(|e| format!("Failed to write config: {}", e))?;
-    Ok(())
+    write_atomic(path, json.as_bytes())
+}
+
+/// Replace `path` with `contents` through a temporary file, so a crash
+/// mid-write never leaves a truncated file behind.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let mut file = fs::File::create(&tmp_path)
+        .map_err(|e| format!("Failed to write {}: {}", tmp_path.display(), e))?;
+    file.write_all(contents)
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("Failed to write {}: {}", tmp_path.display(), e))?;
+    drop(file);
+
+    fs::rename(&tmp_path, path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
 }
 
 pub fn ensure_dirs() {
+    migrate_legacy_dir();
+
     let _ = fs::create_dir_all(config_dir());
+    let _ = fs::create_dir_all(data_dir());
+    let _ = fs::create_dir_all(state_dir());
     let _ = fs::create_dir_all(models_dir());
     let _ = fs::create_dir_all(parakeet_models_dir());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn home_override_holds_every_directory() {
+        let home = PathBuf::from("/tmp/lightwhisper-home");
+        for xdg_base in [dirs::config_dir, dirs::data_dir, dirs::state_dir] {
+            assert_eq!(app_dir_in(Some(home.clone()), xdg_base), home);
+        }
+    }
+
+    #[test]
+    fn xdg_base_is_used_on_linux() {
+        let dir = app_dir_in(None, || Some(PathBuf::from("/xdg/config")));
+        if cfg!(target_os = "linux") {
+            assert_eq!(dir, Path::new("/xdg/config").join(APP_DIR_NAME));
+        } else {
+            assert_eq!(dir, legacy_dir());
+        }
+        assert_eq!(app_dir_in(None, || None), legacy_dir());
+    }
+
+    #[test]
+    fn legacy_entries_move_to_config_and_data() {
+        let root = tempfile::tempdir().unwrap();
+        let (legacy, config, data) = (
+            root.path().join("legacy"),
+            root.path().join("config"),
+            root.path().join("data"),
+        );
+        fs::create_dir_all(legacy.join("models")).unwrap();
+        fs::write(legacy.join("config.json"), "{}").unwrap();
+        fs::write(legacy.join("config.json.bak-1"), "{}").unwrap();
+        fs::write(legacy.join("models/ggml-base.bin"), "model").unwrap();
+        fs::write(legacy.join("history.jsonl"), "old").unwrap();
+        // Already migrated; must not be overwritten
+        fs::create_dir_all(&data).unwrap();
+        fs::write(data.join("history.jsonl"), "new").unwrap();
+
+        migrate_dir(&legacy, &config, &data);
+
+        assert!(config.join("config.json").is_file());
+        assert!(config.join("config.json.bak-1").is_file());
+        assert_eq!(fs::read_to_string(data.join("models/ggml-base.bin")).unwrap(), "model");
+        assert_eq!(fs::read_to_string(data.join("history.jsonl")).unwrap(), "new");
+        // The skipped entry keeps the legacy directory alive
+        assert!(legacy.join("history.jsonl").is_file());
+        assert!(!legacy.join("models").exists());
+    }
+
+    #[test]
+    fn legacy_dir_is_removed_once_empty() {
+        let root = tempfile::tempdir().unwrap();
+        let legacy = root.path().join("legacy");
+        fs::create_dir_all(&legacy).unwrap();
+        fs::write(legacy.join("config.json"), "{}").unwrap();
+
+        migrate_dir(&legacy, &root.path().join("config"), &root.path().join("data"));
+
+        assert!(!legacy.exists());
+    }
+
+    // The temp dir is a single filesystem, so the cross-filesystem copy is
+    // exercised through `copy_into_place` directly
+
+    #[test]
+    fn copy_replaces_leftover_tmp() {
+        let root = tempfile::tempdir().unwrap();
+        let from = root.path().join("models");
+        let to = root.path().join("models-dest");
+        let tmp = root.path().join("models-dest.tmp");
+        fs::create_dir_all(&from).unwrap();
+        fs::write(from.join("ggml-base.bin"), "complete model").unwrap();
+        // What an interrupted copy leaves behind
+        fs::create_dir_all(&tmp).unwrap();
+        fs::write(tmp.join("ggml-base.bin"), "comp").unwrap();
+
+        copy_into_place(&from, &to).unwrap();
+
+        assert_eq!(fs::read_to_string(to.join("ggml-base.bin")).unwrap(), "complete model");
+        assert!(!tmp.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failed_copy_leaves_no_destination() {
+        let root = tempfile::tempdir().unwrap();
+        let from = root.path().join("models");
+        let to = root.path().join("models-dest");
+        fs::create_dir_all(&from).unwrap();
+        fs::write(from.join("a.bin"), "a").unwrap();
+        // Copying a dangling symlink fails partway through the tree
+        std::os::unix::fs::symlink(root.path().join("missing"), from.join("b.bin")).unwrap();
+
+        assert!(copy_into_place(&from, &to).is_err());
+        assert!(!to.exists());
+        assert!(!root.path().join("models-dest.tmp").exists());
+
+        // Once the source is readable the copy is redone, not skipped
+        fs::write(root.path().join("missing"), "b").unwrap();
+        copy_into_place(&from, &to).unwrap();
+        assert_eq!(fs::read_to_string(to.join("b.bin")).unwrap(), "b");
+    }
+
+    fn invalid_fields(config: &AppConfig) -> Vec<String> {
+        match config.validate() {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors.into_iter().map(|e| e.field).collect(),
+        }
+    }
+
+    #[test]
+    fn default_config_is_valid() {
+        assert_eq!(invalid_fields(&AppConfig::default()), Vec::<String>::new());
+    }
+
+    #[test]
+    fn each_invalid_field_is_named() {
+        let mut config = AppConfig::default();
+        let mut second = config.profiles[0].clone();
+        second.audio_device = " ".to_string();
+        config.profiles.push(second);
+        config.active_profile = "Missing".to_string();
+        config.bindings.push(KeyBinding::new("Alt+Space", Action::Cancel));
+        config.bindings.push(KeyBinding::new(
+            "Ctrl+Alt+P",
+            Action::SwitchProfile {
+                profile: "Missing".to_string(),
+            },
+        ));
+        config.voice_commands.commands = vec![
+            crate::voice::VoiceCommand {
+                phrase: "Press Enter".to_string(),
+                action: CommandAction::Keys {
+                    keys: "Ctrl+Nope".to_string(),
+                },
+            },
+            crate::voice::VoiceCommand {
+                phrase: "press enter!".to_string(),
+                action: CommandAction::Shell {
+                    command: " ".to_string(),
+                },
+            },
+        ];
+        config.outputs = vec![Output::Paste, Output::Type, Output::File {
+            path: String::new(),
+        }];
+        config.fallback_engine = Some(Engine::Remote);
+        config.remote.url = "localhost:8000".to_string();
+        config.remote.timeout_secs = 0;
+        config.app_rules = vec![
+            AppRule {
+                app: "kitty".to_string(),
+                paste_chord: Some("Ctrl+Shift+Nope".to_string()),
+                ..AppRule::default()
+            },
+            AppRule {
+                app: "Kitty".to_string(),
+                postprocess_profile: Some("Missing".to_string()),
+                ..AppRule::default()
+            },
+        ];
+
+        assert_eq!(
+            invalid_fields(&config),
+            [
+                "profiles[1].name",
+                "profiles[1].audio_device",
+                "active_profile",
+                "bindings[2].shortcut",
+                "bindings[3].profile",
+                "voice_commands.commands[0].keys",
+                "voice_commands.commands[1].phrase",
+                "voice_commands.commands[1].command",
+                "outputs",
+                "outputs[2].path",
+                "remote.url",
+                "remote.timeout_secs",
+                "app_rules[0].paste_chord",
+                "app_rules[1].app",
+                "app_rules[1].postprocess_profile",
+            ]
+        );
+    }
+
+    #[test]
+    fn fallback_must_differ_from_the_active_profile() {
+        let mut config = AppConfig::default();
+        config.fallback_engine = Some(config.active().engine);
+        config.fallback_model_size = config.active().model_size;
+        assert_eq!(invalid_fields(&config), ["fallback_engine"]);
+
+        config.fallback_model_size = ModelSize::Tiny;
+        assert_eq!(invalid_fields(&config), Vec::<String>::new());
+
+        config.profiles.clear();
+        assert_eq!(invalid_fields(&config), ["profiles", "active_profile"]);
+    }
+
+    #[test]
+    fn v0_strings_are_normalized() {
+        let v1 = migrate_v0_to_v1(serde_json::json!({
+            "engine": " Parakeet ",
+            "model_size": "Small",
+            "fallback_model_size": "TINY",
+            "language": "FR",
+            "fallback_engine": "",
+            "audio_device": "USB Mic",
+        }));
+        assert_eq!(
+            v1,
+            serde_json::json!({
+                "engine": "parakeet",
+                "model_size": "small",
+                "fallback_model_size": "tiny",
+                "language": "fr",
+                "fallback_engine": null,
+                "audio_device": "USB Mic",
+            })
+        );
+    }
+
+    #[test]
+    fn v1_settings_move_into_a_default_profile() {
+        let v2 = migrate_v1_to_v2(serde_json::json!({
+            "version": 1,
+            "engine": "whisper",
+            "model_size": "small",
+            "language": "fr",
+            "audio_device": "USB Mic",
+            "shortcut": "Alt+Space",
+        }));
+        assert_eq!(
+            v2,
+            serde_json::json!({
+                "version": 1,
+                "shortcut": "Alt+Space",
+                "profiles": [{
+                    "name": "Default",
+                    "engine": "whisper",
+                    "model_size": "small",
+                    "language": "fr",
+                    "audio_device": "USB Mic",
+                }],
+                "active_profile": "Default",
+            })
+        );
+    }
+
+    #[test]
+    fn v2_shortcut_becomes_bindings() {
+        let v3 = migrate_v2_to_v3(serde_json::json!({ "shortcut": "Ctrl+Alt+R" }));
+        assert_eq!(
+            v3,
+            serde_json::json!({
+                "bindings": [
+                    { "shortcut": "Ctrl+Alt+R", "action": "toggle" },
+                    { "shortcut": "Escape", "action": "cancel" },
+                ],
+            })
+        );
+        let v3 = migrate_v2_to_v3(serde_json::json!({}));
+        assert_eq!(v3["bindings"][0]["shortcut"], "Alt+Space");
+    }
+
+    /// Load a fixture as `config.json` in a fresh directory.
+    fn load_fixture(content: &str) -> (tempfile::TempDir, LoadedConfig) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        fs::write(&path, content).unwrap();
+        let loaded = load_config_from(&path);
+        (dir, loaded)
+    }
+
+    fn backups(dir: &Path) -> Vec<PathBuf> {
+        fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .filter(|p| {
+                p.file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .strip_prefix("config.json.bak-")
+                    .is_some_and(|t| !t.is_empty() && t.bytes().all(|b| b.is_ascii_digit()))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn every_old_version_loads() {
+        let (dir, loaded) = load_fixture(include_str!("../tests/fixtures/config-v0.json"));
+        assert_eq!(loaded.recovered, None);
+        let config = loaded.config;
+        assert_eq!(config.active().engine, Engine::Whisper);
+        assert_eq!(config.active().model_size, ModelSize::Small);
+        assert_eq!(config.active().language.as_str(), "fr");
+        assert_eq!(config.fallback_engine, None);
+        assert_eq!(config.fallback_model_size, ModelSize::Tiny);
+        assert_eq!(config.bindings[0], KeyBinding::new("Ctrl+Shift+D", Action::Toggle));
+        // Saved in the current layout, without a backup
+        let saved: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(dir.path().join("config.json")).unwrap())
+                .unwrap();
+        assert_eq!(saved["version"], CONFIG_VERSION);
+        assert!(backups(dir.path()).is_empty());
+
+        let (_dir, loaded) = load_fixture(include_str!("../tests/fixtures/config-v1.json"));
+        assert_eq!(loaded.recovered, None);
+        let config = loaded.config;
+        assert_eq!(config.profiles.len(), 1);
+        assert_eq!(config.active().engine, Engine::Parakeet);
+        assert_eq!(config.active().audio_device, "USB Microphone");
+        assert_eq!(config.fallback_engine, Some(Engine::Whisper));
+        assert_eq!(config.bindings[0], KeyBinding::new("Super+D", Action::Toggle));
+
+        let (_dir, loaded) = load_fixture(include_str!("../tests/fixtures/config-v2.json"));
+        assert_eq!(loaded.recovered, None);
+        let config = loaded.config;
+        assert_eq!(config.profiles.len(), 2);
+        assert_eq!(config.active().name, "Meetings");
+        assert_eq!(config.active().prompt, "Quarterly review");
+        assert_eq!(config.bindings, [
+            KeyBinding::new("Ctrl+Alt+R", Action::Toggle),
+            KeyBinding::new("Escape", Action::Cancel),
+        ]);
+    }
+
+    #[test]
+    fn bad_field_keeps_its_default_only() {
+        let (config, errors) = parse_config(&serde_json::json!({
+            "version": CONFIG_VERSION,
+            "undo_method": "sideways",
+            "fallback_eager": true,
+            "fallback_model_size": "small",
+        }));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "undo_method");
+        assert_eq!(config.undo_method, UndoMethod::Backspace);
+        assert!(config.fallback_eager);
+        assert_eq!(config.fallback_model_size, ModelSize::Small);
+
+        let (config, errors) = parse_config(&serde_json::json!({ "profiles": [] }));
+        assert_eq!(errors[0].field, "profiles");
+        assert_eq!(config.profiles.len(), 1);
+    }
+
+    #[test]
+    fn repaired_config_is_backed_up() {
+        let original = r#"{ "version": 3, "undo_method": "sideways", "fallback_eager": true }"#;
+        let (dir, loaded) = load_fixture(original);
+        assert!(loaded.config.fallback_eager);
+
+        let saved = backups(dir.path());
+        assert_eq!(saved.len(), 1);
+        assert_eq!(fs::read_to_string(&saved[0]).unwrap(), original);
+        let notice = loaded.recovered.unwrap();
+        assert!(notice.contains("undo_method"));
+        assert!(notice.contains(&saved[0].display().to_string()));
+
+        let (dir, loaded) = load_fixture("{ not json");
+        assert_eq!(loaded.config, AppConfig::default());
+        assert_eq!(backups(dir.path()).len(), 1);
+    }
+}
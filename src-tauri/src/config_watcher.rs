@@ -0,0 +1,86 @@
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::sync::mpsc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::config;
+use crate::recording;
+use crate::shortcuts;
+use crate::state::{apply_config_change, AppState};
+
+/// Editors and dotfile tools often touch the file several times per save
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Watch the config directory and re-apply external edits of `config.json`
+/// to the running app. Our own saves are no-ops here: by the time the event
+/// arrives the in-memory config already matches the file.
+pub fn start(app: &AppHandle) -> Result<(), String> {
+    let (tx, rx) = mpsc::channel::<notify::Result<notify::Event>>();
+    let mut watcher = notify::recommended_watcher(tx)
+        .map_err(|e| format!("Failed to create config watcher: {}", e))?;
+    watcher
+        .watch(&config::config_dir(), RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch config dir: {}", e))?;
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        // The watcher stops when dropped, so it lives as long as this thread
+        let _watcher = watcher;
+
+        while let Ok(event) = rx.recv() {
+            if !touches_config(&event) {
+                continue;
+            }
+
+            // Swallow the burst of events belonging to the same save
+            std::thread::sleep(DEBOUNCE);
+            while rx.try_recv().is_ok() {}
+
+            reload(&app);
+        }
+    });
+
+    Ok(())
+}
+
+fn touches_config(event: &notify::Result<notify::Event>) -> bool {
+    let Ok(event) = event else {
+        return false;
+    };
+    if matches!(event.kind, EventKind::Access(_) | EventKind::Remove(_)) {
+        return false;
+    }
+    let config_path = config::config_path();
+    event
+        .paths
+        .iter()
+        .any(|p| p.file_name() == config_path.file_name())
+}
+
+fn reload(app: &AppHandle) {
+    let new_config = match config::read_config_file() {
+        Ok(config) => config,
+        Err(e) => {
+            recording::emit_error(app, &format!("Ignored external edit of config.json: {}", e));
+            return;
+        }
+    };
+
+    let state = app.state::<AppState>();
+    let old_config = {
+        let mut current = state.config.lock().unwrap();
+        if *current == new_config {
+            return;
+        }
+        // Nothing is applied unless every shortcut can be registered
+        if let Err(e) = shortcuts::rebind(app, &current.bindings, &new_config.bindings) {
+            drop(current);
+            recording::emit_error(app, &format!("Ignored external edit of config.json: {}", e));
+            return;
+        }
+        std::mem::replace(&mut *current, new_config.clone())
+    };
+
+    eprintln!("config.json changed on disk, applying");
+    apply_config_change(app, &old_config, &new_config);
+}
@@ -0,0 +1,509 @@
+use regex::{NoExpand, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::{self, Language};
+use crate::postprocess::Context;
+
+/// A find/replace rule applied to every transcript.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DictionaryRule {
+    /// Assigned when the rule is added; ignored on input
+    #[serde(default)]
+    pub id: u64,
+    pub find: String,
+    pub replace: String,
+    /// Treat `find` as a regular expression and allow `$1`-style
+    /// references in `replace`. Literals match whole words only.
+    #[serde(default)]
+    pub regex: bool,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// Only applied when dictating in this language
+    #[serde(default)]
+    pub language: Option<Language>,
+    /// Only applied in this profile
+    #[serde(default)]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DictionaryFile {
+    rules: Vec<DictionaryRule>,
+}
+
+pub fn dictionary_path() -> PathBuf {
+    config::config_dir().join("dictionary.json")
+}
+
+/// All rules, in the order they are applied. A missing file is an empty
+/// dictionary.
+pub fn load() -> Result<Vec<DictionaryRule>, String> {
+    let content = match fs::read_to_string(dictionary_path()) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read dictionary: {}", e)),
+    };
+    let file: DictionaryFile =
+        serde_json::from_str(&content).map_err(|e| format!("Invalid dictionary.json: {}", e))?;
+    Ok(file.rules)
+}
+
+fn save(rules: &[DictionaryRule]) -> Result<(), String> {
+    let file = DictionaryFile {
+        rules: rules.to_vec(),
+    };
+    let json =
+        serde_json::to_string_pretty(&file).map_err(|e| format!("Failed to serialize: {}", e))?;
+    config::write_atomic(&dictionary_path(), json.as_bytes())
+}
+
+impl DictionaryRule {
+    fn matcher(&self) -> Result<Regex, String> {
+        let pattern = if self.regex {
+            self.find.clone()
+        } else {
+            // Only anchor on word characters, so "C++" still matches
+            let boundary = |c: Option<char>| match c {
+                Some(c) if c.is_alphanumeric() || c == '_' => r"\b",
+                _ => "",
+            };
+            format!(
+                "{}{}{}",
+                boundary(self.find.chars().next()),
+                regex::escape(&self.find),
+                boundary(self.find.chars().last()),
+            )
+        };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!self.case_sensitive)
+            .build()
+            .map_err(|e| format!("Invalid pattern `{}`: {}", self.find, e))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.find.is_empty() {
+            return Err("The text to find is empty".to_string());
+        }
+        self.matcher().map(|_| ())
+    }
+
+    fn applies(&self, ctx: &Context) -> bool {
+        let language_ok = match (&self.language, ctx.language.code()) {
+            (Some(rule), Some(dictated)) => rule.as_str() == dictated,
+            // Auto-detected dictations may be in any language
+            _ => true,
+        };
+        let profile_ok = self.profile.as_deref().is_none_or(|p| p == ctx.profile);
+        language_ok && profile_ok
+    }
+}
+
+/// Apply every rule in scope for `ctx` to `text`, in order.
+pub fn apply(rules: &[DictionaryRule], text: &str, ctx: &Context) -> String {
+    let mut text = text.to_string();
+    for rule in rules.iter().filter(|r| r.applies(ctx)) {
+        let matcher = match rule.matcher() {
+            Ok(matcher) => matcher,
+            Err(e) => {
+                eprintln!("Skipping dictionary rule {}: {}", rule.id, e);
+                continue;
+            }
+        };
+        text = if rule.regex {
+            matcher
+                .replace_all(&text, rule.replace.as_str())
+                .into_owned()
+        } else {
+            matcher
+                .replace_all(&text, NoExpand(&rule.replace))
+                .into_owned()
+        };
+    }
+    text
+}
+
+fn next_id(rules: &[DictionaryRule]) -> u64 {
+    rules.iter().map(|r| r.id).max().unwrap_or(0) + 1
+}
+
+/// Append `rule` with a fresh id and return it as stored.
+pub fn add(mut rule: DictionaryRule) -> Result<DictionaryRule, String> {
+    rule.validate()?;
+    let mut rules = load()?;
+    rule.id = next_id(&rules);
+    rules.push(rule.clone());
+    save(&rules)?;
+    Ok(rule)
+}
+
+/// Replace the rule with the same id.
+pub fn update(rule: DictionaryRule) -> Result<(), String> {
+    rule.validate()?;
+    let mut rules = load()?;
+    let slot = rules
+        .iter_mut()
+        .find(|r| r.id == rule.id)
+        .ok_or_else(|| format!("No dictionary rule with id {}", rule.id))?;
+    *slot = rule;
+    save(&rules)
+}
+
+pub fn delete(id: u64) -> Result<(), String> {
+    let mut rules = load()?;
+    let before = rules.len();
+    rules.retain(|r| r.id != id);
+    if rules.len() == before {
+        return Err(format!("No dictionary rule with id {}", id));
+    }
+    save(&rules)
+}
+
+// ── Import / export ──
+//
+// The format follows the file extension: `.json` holds the same
+// `{"rules": [...]}` object as dictionary.json, `.csv` has a header row with
+// the columns below (only `find` and `replace` are required).
+
+const CSV_COLUMNS: &[&str] = &[
+    "find",
+    "replace",
+    "regex",
+    "case_sensitive",
+    "language",
+    "profile",
+];
+
+enum Format {
+    Json,
+    Csv,
+}
+
+fn format_of(path: &Path) -> Result<Format, String> {
+    match path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .as_deref()
+    {
+        Some("json") => Ok(Format::Json),
+        Some("csv") => Ok(Format::Csv),
+        _ => Err(format!(
+            "Cannot tell the format of {}: use a .csv or .json file",
+            path.display()
+        )),
+    }
+}
+
+/// Read rules from `path` and add them, or replace the dictionary with them
+/// when `replace` is set. Nothing is saved if any rule is invalid. Returns
+/// the number of rules imported.
+pub fn import(path: &Path, replace: bool) -> Result<usize, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let imported = match format_of(path)? {
+        Format::Json => {
+            serde_json::from_str::<DictionaryFile>(&content)
+                .map_err(|e| format!("Invalid JSON dictionary: {}", e))?
+                .rules
+        }
+        Format::Csv => from_csv(&content)?,
+    };
+    for (i, rule) in imported.iter().enumerate() {
+        rule.validate()
+            .map_err(|e| format!("Rule {} (`{}`): {}", i + 1, rule.find, e))?;
+    }
+
+    let mut rules = if replace { Vec::new() } else { load()? };
+    let count = imported.len();
+    for mut rule in imported {
+        rule.id = next_id(&rules);
+        rules.push(rule);
+    }
+    save(&rules)?;
+    Ok(count)
+}
+
+/// Write the dictionary to `path`. Returns the number of rules exported.
+pub fn export(path: &Path) -> Result<usize, String> {
+    let rules = load()?;
+    let content = match format_of(path)? {
+        Format::Json => serde_json::to_string_pretty(&DictionaryFile {
+            rules: rules.clone(),
+        })
+        .map_err(|e| format!("Failed to serialize: {}", e))?,
+        Format::Csv => to_csv(&rules),
+    };
+    config::write_atomic(path, content.as_bytes())?;
+    Ok(rules.len())
+}
+
+fn to_csv(rules: &[DictionaryRule]) -> String {
+    let mut out = CSV_COLUMNS.join(",");
+    out.push('\n');
+    for rule in rules {
+        let fields = [
+            rule.find.clone(),
+            rule.replace.clone(),
+            rule.regex.to_string(),
+            rule.case_sensitive.to_string(),
+            rule.language
+                .as_ref()
+                .map(|l| l.to_string())
+                .unwrap_or_default(),
+            rule.profile.clone().unwrap_or_default(),
+        ];
+        let escaped: Vec<String> = fields.iter().map(|f| csv_escape(f)).collect();
+        out.push_str(&escaped.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// One CSV record and the line of the file it starts on.
+struct Record {
+    line: usize,
+    fields: Vec<String>,
+}
+
+/// Split CSV text into records of fields, honouring quoted fields with
+/// embedded commas, quotes (`""`) and line breaks.
+fn parse_csv(content: &str) -> Result<Vec<Record>, String> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut start = 1;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == '\n' {
+            line += 1;
+        }
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' => in_quotes = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(Record {
+                    line: start,
+                    fields: std::mem::take(&mut record),
+                });
+                start = line;
+            }
+            _ => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(format!("Line {}: unterminated quoted field", start));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(Record {
+            line: start,
+            fields: record,
+        });
+    }
+    // Blank lines
+    records.retain(|r| !(r.fields.len() == 1 && r.fields[0].is_empty()));
+    Ok(records)
+}
+
+fn from_csv(content: &str) -> Result<Vec<DictionaryRule>, String> {
+    let mut records = parse_csv(content)?.into_iter();
+    let header: Vec<String> = records
+        .next()
+        .ok_or("The CSV file is empty")?
+        .fields
+        .iter()
+        .map(|h| h.trim().to_ascii_lowercase())
+        .collect();
+    let column = |name: &str| header.iter().position(|h| h == name);
+    let (Some(find_col), Some(replace_col)) = (column("find"), column("replace")) else {
+        return Err(format!(
+            "The CSV header must name `find` and `replace` columns (known columns: {})",
+            CSV_COLUMNS.join(", ")
+        ));
+    };
+
+    let mut rules = Vec::new();
+    for Record { line, fields: record } in records {
+        let get = |col: Option<usize>| {
+            col.and_then(|c| record.get(c))
+                .map(|v| v.trim())
+                .unwrap_or("")
+        };
+        let flag = |name: &str| -> Result<bool, String> {
+            match get(column(name)).to_ascii_lowercase().as_str() {
+                "" | "false" | "0" | "no" => Ok(false),
+                "true" | "1" | "yes" => Ok(true),
+                other => Err(format!(
+                    "Line {}: `{}` is not a valid {}",
+                    line, other, name
+                )),
+            }
+        };
+        let language = match get(column("language")) {
+            "" => None,
+            code => Some(
+                Language::try_from(code.to_string())
+                    .map_err(|e| format!("Line {}: {}", line, e))?,
+            ),
+        };
+        let profile = Some(get(column("profile")).to_string()).filter(|p| !p.is_empty());
+
+        rules.push(DictionaryRule {
+            id: 0,
+            // Spaces may be significant in find/replace, so no trimming
+            find: record.get(find_col).cloned().unwrap_or_default(),
+            replace: record.get(replace_col).cloned().unwrap_or_default(),
+            regex: flag("regex")?,
+            case_sensitive: flag("case_sensitive")?,
+            language,
+            profile,
+        });
+    }
+    Ok(rules)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(find: &str, replace: &str) -> DictionaryRule {
+        DictionaryRule {
+            id: 0,
+            find: find.to_string(),
+            replace: replace.to_string(),
+            regex: false,
+            case_sensitive: false,
+            language: None,
+            profile: None,
+        }
+    }
+
+    fn lang(code: &str) -> Language {
+        Language::try_from(code.to_string()).unwrap()
+    }
+
+    fn apply_in(rules: &[DictionaryRule], text: &str, language: &str, profile: &str) -> String {
+        let ctx = Context {
+            language: &lang(language),
+            profile,
+        };
+        apply(rules, text, &ctx)
+    }
+
+    fn fields(content: &str) -> Vec<Vec<String>> {
+        parse_csv(content)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.fields)
+            .collect()
+    }
+
+    #[test]
+    fn csv_quoted_fields() {
+        assert_eq!(
+            fields("find,replace\n\"a, b\",\"say \"\"hi\"\"\"\n\"two\nlines\",x\n\n"),
+            [
+                vec!["find", "replace"],
+                vec!["a, b", "say \"hi\""],
+                vec!["two\nlines", "x"],
+            ]
+        );
+        assert_eq!(fields("find,replace\r\nk8s,Kubernetes"), [["find", "replace"], ["k8s", "Kubernetes"]]);
+    }
+
+    #[test]
+    fn csv_errors_name_the_physical_line() {
+        let content = "find,replace,regex\n\"multi\nline\",x,false\n\nfoo,bar,maybe\n";
+        assert_eq!(
+            from_csv(content).unwrap_err(),
+            "Line 5: `maybe` is not a valid regex"
+        );
+        assert_eq!(
+            parse_csv("find,replace\n\"open,x\n").err().unwrap(),
+            "Line 2: unterminated quoted field"
+        );
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let rules = vec![
+            DictionaryRule {
+                regex: true,
+                case_sensitive: true,
+                ..rule(r"(\d+), (\d+)", "$1 \"and\" $2")
+            },
+            DictionaryRule {
+                language: Some(lang("fr")),
+                profile: Some("Notes, work".to_string()),
+                ..rule(" lol ", "line one\nline two")
+            },
+        ];
+        assert_eq!(from_csv(&to_csv(&rules)).unwrap(), rules);
+    }
+
+    #[test]
+    fn literal_rules_match_whole_words_and_replace_verbatim() {
+        let rules = [rule("cat", "$1 dog"), rule("C++", "Rust")];
+        assert_eq!(
+            apply_in(&rules, "Cat concatenate cat. c++", "auto", "Default"),
+            "$1 dog concatenate $1 dog. Rust"
+        );
+    }
+
+    #[test]
+    fn regex_rules_expand_groups_without_boundaries() {
+        let rules = [DictionaryRule {
+            regex: true,
+            ..rule(r"(\w+)ing", "${1}ed")
+        }];
+        assert_eq!(apply_in(&rules, "walking everything", "auto", "Default"), "walked everythed");
+    }
+
+    #[test]
+    fn rules_are_scoped() {
+        let rules = [
+            DictionaryRule {
+                case_sensitive: true,
+                ..rule("Go", "Golang")
+            },
+            DictionaryRule {
+                language: Some(lang("fr")),
+                ..rule("mail", "courriel")
+            },
+            DictionaryRule {
+                profile: Some("Code".to_string()),
+                ..rule("pr", "pull request")
+            },
+        ];
+        assert_eq!(apply_in(&rules, "go Go mail pr", "en", "Default"), "go Golang mail pr");
+        assert_eq!(apply_in(&rules, "mail pr", "fr", "Code"), "courriel pull request");
+        // Auto-detected dictations get every language's rules
+        assert_eq!(apply_in(&rules, "mail", "auto", "Default"), "courriel");
+    }
+}
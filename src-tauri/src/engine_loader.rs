@@ -0,0 +1,119 @@
+use std::sync::atomic::Ordering;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::config::{AppConfig, Engine, ModelSize};
+use crate::model_manager;
+use crate::state::{get_model_path, AppState};
+use crate::stt::SttEngine;
+
+/// Load `engine` with `model_size` on a background thread.
+///
+/// The engine currently held in `AppState` keeps serving transcriptions while
+/// the new one loads, and is only swapped out once loading succeeded.
+/// Emits `engine-loading`, then either `engine-ready` or `engine-error`.
+pub fn spawn_load(app: &AppHandle, engine: Engine, model_size: ModelSize) {
+    let state = app.state::<AppState>();
+    let generation = state.engine_generation.fetch_add(1, Ordering::SeqCst) + 1;
+    state.engine_loading.store(true, Ordering::SeqCst);
+
+    let _ = app.emit(
+        "engine-loading",
+        serde_json::json!({
+            "engine": engine,
+            "model_size": model_size,
+        }),
+    );
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let result = load_engine(engine, model_size);
+        let state = app.state::<AppState>();
+
+        let mut current = state.engine.lock().unwrap();
+        // A newer load was requested while this one ran — let that one win
+        if state.engine_generation.load(Ordering::SeqCst) != generation {
+            return;
+        }
+
+        match result {
+            Ok(loaded) => {
+                *current = loaded;
+                drop(current);
+                state.engine_loading.store(false, Ordering::SeqCst);
+                eprintln!("STT engine [{}] loaded", engine);
+                let _ = app.emit(
+                    "engine-ready",
+                    serde_json::json!({
+                        "engine": engine,
+                        "model_size": model_size,
+                    }),
+                );
+            }
+            Err(e) => {
+                drop(current);
+                state.engine_loading.store(false, Ordering::SeqCst);
+                eprintln!("Failed to load STT engine [{}]: {}", engine, e);
+                let _ = app.emit(
+                    "engine-error",
+                    serde_json::json!({
+                        "engine": engine,
+                        "model_size": model_size,
+                        "error": e,
+                    }),
+                );
+            }
+        }
+    });
+}
+
+/// Load the configured fallback engine on a background thread.
+///
+/// Any previously loaded fallback is dropped first so a config change never
+/// leaves a stale one around. Does nothing when no fallback is configured;
+/// when `fallback_eager` is off the fallback is loaded on first use instead.
+pub fn spawn_fallback_load(app: &AppHandle, cfg: &AppConfig) {
+    let state = app.state::<AppState>();
+    *state.fallback_engine.lock().unwrap() = None;
+
+    let Some(engine_name) = cfg.fallback_engine else {
+        return;
+    };
+    if !cfg.fallback_eager {
+        return;
+    }
+
+    let model_size = cfg.fallback_model_size;
+    let app = app.clone();
+    std::thread::spawn(move || match load_engine(engine_name, model_size) {
+        Ok(engine) => {
+            let state = app.state::<AppState>();
+            let mut slot = state.fallback_engine.lock().unwrap();
+            let cfg = state.config.lock().unwrap();
+            // Skip if a lazy load got there first or the config moved on
+            let still_wanted =
+                cfg.fallback_engine == Some(engine_name) && cfg.fallback_model_size == model_size;
+            if slot.is_none() && still_wanted {
+                *slot = Some(engine);
+                eprintln!("Fallback STT engine [{}] loaded", engine_name);
+            }
+        }
+        Err(e) => eprintln!("Failed to load fallback STT engine [{}]: {}", engine_name, e),
+    });
+}
+
+/// Build and load a fresh engine without touching `AppState`.
+pub fn load_engine(engine_name: Engine, model_size: ModelSize) -> Result<SttEngine, String> {
+    let model_path = get_model_path(engine_name, model_size);
+    if !model_manager::model_exists_for_engine(engine_name, model_size) {
+        return Err(format!(
+            "No model found for engine '{}' — download it in Settings",
+            engine_name
+        ));
+    }
+
+    let mut engine = SttEngine::new(engine_name);
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| engine.load_model(&model_path)))
+        .unwrap_or_else(|_| Err("Model loading crashed".to_string()))?;
+
+    Ok(engine)
+}
@@ -0,0 +1,479 @@
+// ── Focus capture and restore, so the paste lands where the user was typing ──
+
+/// The app, and on X11 the window, that had focus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FocusTarget {
+    /// Process id; -1 when unknown
+    pub pid: i32,
+    /// X11 window id; 0 when unknown or not on X11
+    pub window: u32,
+}
+
+impl Default for FocusTarget {
+    fn default() -> Self {
+        Self { pid: -1, window: 0 }
+    }
+}
+
+impl FocusTarget {
+    pub fn is_known(&self) -> bool {
+        self.pid > 0 || self.window != 0
+    }
+}
+
+/// Names of a focused app, for per-app rules. Empty when unknown.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AppIdentity {
+    /// Executable name, e.g. `kitty`
+    pub process: String,
+    /// X11 `WM_CLASS` instance and class, e.g. `gnome-terminal-server` and
+    /// `Gnome-terminal`
+    pub wm_class: Vec<String>,
+    /// macOS bundle id, e.g. `com.apple.Terminal`
+    pub bundle_id: String,
+}
+
+impl AppIdentity {
+    /// Every known name of the app.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.process.as_str())
+            .chain(self.wm_class.iter().map(String::as_str))
+            .chain(std::iter::once(self.bundle_id.as_str()))
+            .filter(|n| !n.is_empty())
+    }
+}
+
+/// Look up the names of the app behind `target`.
+pub fn identify(target: FocusTarget) -> AppIdentity {
+    let mut app = AppIdentity::default();
+
+    #[cfg(target_os = "macos")]
+    if target.pid > 0 {
+        app.process = macos_focus::process_name(target.pid).unwrap_or_default();
+        app.bundle_id = macos_focus::bundle_id(target.pid).unwrap_or_default();
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        if target.pid > 0 {
+            app.process = process_name(target.pid).unwrap_or_default();
+        }
+        if target.window != 0 {
+            app.wm_class = x11_focus::wm_class(target.window).unwrap_or_else(|e| {
+                eprintln!("Cannot read the window class: {}", e);
+                Vec::new()
+            });
+        }
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    let _ = target;
+
+    app
+}
+
+/// Executable name of process `pid`. `/proc/<pid>/comm` cuts names to 15
+/// bytes ("gnome-terminal-"), so it only serves when neither the executable
+/// nor the command line can be read.
+#[cfg(target_os = "linux")]
+fn process_name(pid: i32) -> Option<String> {
+    use std::path::Path;
+
+    let base_name = |path: &Path| {
+        let name = path.file_name()?.to_string_lossy();
+        // The link of an executable replaced by an update
+        Some(name.trim_end_matches(" (deleted)").to_string())
+    };
+    std::fs::read_link(format!("/proc/{}/exe", pid))
+        .ok()
+        .and_then(|exe| base_name(&exe))
+        .or_else(|| {
+            let cmdline = std::fs::read(format!("/proc/{}/cmdline", pid)).ok()?;
+            let program = cmdline.split(|&b| b == 0).next().filter(|p| !p.is_empty())?;
+            base_name(Path::new(&*String::from_utf8_lossy(program)))
+        })
+        .or_else(|| {
+            std::fs::read_to_string(format!("/proc/{}/comm", pid))
+                .ok()
+                .map(|name| name.trim_end().to_string())
+        })
+        .filter(|name| !name.is_empty())
+}
+
+/// What has focus right now.
+pub fn frontmost() -> FocusTarget {
+    #[cfg(target_os = "macos")]
+    {
+        FocusTarget {
+            pid: macos_focus::get_frontmost_pid(),
+            window: 0,
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        x11_focus::frontmost().unwrap_or_else(|e| {
+            // Without DISPLAY there is no X11 to ask; anything else is worth a note
+            if std::env::var_os("DISPLAY").is_some() {
+                eprintln!("Cannot read the focused window: {}", e);
+            }
+            FocusTarget::default()
+        })
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    {
+        FocusTarget::default()
+    }
+}
+
+/// Give focus back to `target`. Does nothing when it is unknown.
+pub fn activate(target: FocusTarget) {
+    #[cfg(target_os = "macos")]
+    if target.pid > 0 {
+        macos_focus::activate_pid(target.pid);
+    }
+
+    #[cfg(target_os = "linux")]
+    if target.window != 0 {
+        if let Err(e) = x11_focus::activate(target.window) {
+            eprintln!("Failed to restore focus: {}", e);
+        }
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    let _ = target;
+}
+
+// ── macOS via ObjC runtime (no extra deps, no permissions needed) ──
+
+#[cfg(target_os = "macos")]
+mod macos_focus {
+    use std::ffi::{c_char, c_void, CStr};
+
+    extern "C" {
+        fn objc_getClass(name: *const u8) -> *mut c_void;
+        fn sel_registerName(name: *const u8) -> *mut c_void;
+        fn objc_msgSend();
+    }
+
+    /// Get the PID of the currently frontmost application.
+    pub fn get_frontmost_pid() -> i32 {
+        unsafe {
+            type SendObj = unsafe extern "C" fn(*mut c_void, *mut c_void) -> *mut c_void;
+            type SendI32 = unsafe extern "C" fn(*mut c_void, *mut c_void) -> i32;
+            let send_obj: SendObj = std::mem::transmute(objc_msgSend as *const ());
+            let send_i32: SendI32 = std::mem::transmute(objc_msgSend as *const ());
+
+            let cls = objc_getClass(b"NSWorkspace\0".as_ptr());
+            let workspace = send_obj(cls, sel_registerName(b"sharedWorkspace\0".as_ptr()));
+            let app = send_obj(
+                workspace,
+                sel_registerName(b"frontmostApplication\0".as_ptr()),
+            );
+            if app.is_null() {
+                return -1;
+            }
+            send_i32(app, sel_registerName(b"processIdentifier\0".as_ptr()))
+        }
+    }
+
+    /// Executable name of a process.
+    pub fn process_name(pid: i32) -> Option<String> {
+        let mut buffer = [0u8; 256];
+        let len = unsafe {
+            libc::proc_name(pid, buffer.as_mut_ptr() as *mut c_void, buffer.len() as u32)
+        };
+        if len <= 0 {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&buffer[..len as usize]).into_owned())
+    }
+
+    /// Bundle identifier of a running application, e.g. `com.apple.Terminal`.
+    pub fn bundle_id(pid: i32) -> Option<String> {
+        unsafe {
+            type SendWithI32 = unsafe extern "C" fn(*mut c_void, *mut c_void, i32) -> *mut c_void;
+            type SendObj = unsafe extern "C" fn(*mut c_void, *mut c_void) -> *mut c_void;
+            type SendStr = unsafe extern "C" fn(*mut c_void, *mut c_void) -> *const c_char;
+            let send_with_i32: SendWithI32 = std::mem::transmute(objc_msgSend as *const ());
+            let send_obj: SendObj = std::mem::transmute(objc_msgSend as *const ());
+            let send_str: SendStr = std::mem::transmute(objc_msgSend as *const ());
+
+            let cls = objc_getClass(b"NSRunningApplication\0".as_ptr());
+            let app = send_with_i32(
+                cls,
+                sel_registerName(b"runningApplicationWithProcessIdentifier:\0".as_ptr()),
+                pid,
+            );
+            if app.is_null() {
+                return None;
+            }
+            let id = send_obj(app, sel_registerName(b"bundleIdentifier\0".as_ptr()));
+            if id.is_null() {
+                return None;
+            }
+            let utf8 = send_str(id, sel_registerName(b"UTF8String\0".as_ptr()));
+            if utf8.is_null() {
+                return None;
+            }
+            Some(CStr::from_ptr(utf8).to_string_lossy().into_owned())
+        }
+    }
+
+    /// Bring an application to the foreground by its PID.
+    pub fn activate_pid(pid: i32) {
+        unsafe {
+            type SendWithI32 = unsafe extern "C" fn(*mut c_void, *mut c_void, i32) -> *mut c_void;
+            type SendWithUsize =
+                unsafe extern "C" fn(*mut c_void, *mut c_void, usize) -> bool;
+            let send_with_i32: SendWithI32 = std::mem::transmute(objc_msgSend as *const ());
+            let send_with_usize: SendWithUsize = std::mem::transmute(objc_msgSend as *const ());
+
+            let cls = objc_getClass(b"NSRunningApplication\0".as_ptr());
+            let app = send_with_i32(
+                cls,
+                sel_registerName(b"runningApplicationWithProcessIdentifier:\0".as_ptr()),
+                pid,
+            );
+            if !app.is_null() {
+                // NSApplicationActivateIgnoringOtherApps = 2
+                send_with_usize(app, sel_registerName(b"activateWithOptions:\0".as_ptr()), 2);
+            }
+        }
+    }
+}
+
+// ── X11 via EWMH `_NET_ACTIVE_WINDOW`, or the input focus without a window manager ──
+
+#[cfg(target_os = "linux")]
+mod x11_focus {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{
+        Atom, AtomEnum, ClientMessageEvent, ConnectionExt as _, EventMask, InputFocus, Window,
+    };
+    use x11rb::rust_connection::RustConnection;
+    use x11rb::{CURRENT_TIME, NONE};
+
+    use super::FocusTarget;
+
+    fn x11_error(e: impl std::fmt::Display) -> String {
+        format!("X11 error: {}", e)
+    }
+
+    struct Display {
+        conn: RustConnection,
+        root: Window,
+        net_active_window: Atom,
+        net_supported: Atom,
+        net_wm_pid: Atom,
+    }
+
+    impl Display {
+        fn open() -> Result<Self, String> {
+            let (conn, screen_num) = x11rb::connect(None).map_err(x11_error)?;
+            let root = conn.setup().roots[screen_num].root;
+            let intern = |name: &[u8]| -> Result<Atom, String> {
+                Ok(conn
+                    .intern_atom(false, name)
+                    .map_err(x11_error)?
+                    .reply()
+                    .map_err(x11_error)?
+                    .atom)
+            };
+            Ok(Self {
+                net_active_window: intern(b"_NET_ACTIVE_WINDOW")?,
+                net_supported: intern(b"_NET_SUPPORTED")?,
+                net_wm_pid: intern(b"_NET_WM_PID")?,
+                root,
+                conn,
+            })
+        }
+
+        /// 32-bit values of `property` on `window`; empty when unset.
+        fn property32(
+            &self,
+            window: Window,
+            property: Atom,
+            kind: AtomEnum,
+        ) -> Result<Vec<u32>, String> {
+            let reply = self
+                .conn
+                .get_property(false, window, property, kind, 0, 1024)
+                .map_err(x11_error)?
+                .reply()
+                .map_err(x11_error)?;
+            Ok(reply.value32().map(|v| v.collect()).unwrap_or_default())
+        }
+
+        /// Whether a window manager handles `_NET_ACTIVE_WINDOW` requests.
+        fn has_ewmh(&self) -> Result<bool, String> {
+            let supported = self.property32(self.root, self.net_supported, AtomEnum::ATOM)?;
+            Ok(supported.contains(&self.net_active_window))
+        }
+    }
+
+    pub fn frontmost() -> Result<FocusTarget, String> {
+        let display = Display::open()?;
+        let active = display.property32(display.root, display.net_active_window, AtomEnum::WINDOW)?;
+        let window = match active.first() {
+            Some(&window) if window != NONE => window,
+            // No EWMH window manager, e.g. a bare Xvfb
+            _ => {
+                let focus = display
+                    .conn
+                    .get_input_focus()
+                    .map_err(x11_error)?
+                    .reply()
+                    .map_err(x11_error)?
+                    .focus;
+                // 0 is None and 1 is PointerRoot, neither a window
+                if focus <= 1 {
+                    return Ok(FocusTarget::default());
+                }
+                focus
+            }
+        };
+        let pid = display
+            .property32(window, display.net_wm_pid, AtomEnum::CARDINAL)?
+            .first()
+            .map(|&pid| pid as i32)
+            .unwrap_or(-1);
+        Ok(FocusTarget { pid, window })
+    }
+
+    /// Instance and class names from `WM_CLASS`; empty when unset.
+    pub fn wm_class(window: Window) -> Result<Vec<String>, String> {
+        let display = Display::open()?;
+        let reply = display
+            .conn
+            .get_property(false, window, AtomEnum::WM_CLASS, AtomEnum::STRING, 0, 256)
+            .map_err(x11_error)?
+            .reply()
+            .map_err(x11_error)?;
+        // Two NUL-terminated strings
+        Ok(reply
+            .value
+            .split(|&b| b == 0)
+            .filter(|s| !s.is_empty())
+            .map(|s| String::from_utf8_lossy(s).into_owned())
+            .collect())
+    }
+
+    pub fn activate(window: Window) -> Result<(), String> {
+        let display = Display::open()?;
+        if display.has_ewmh()? {
+            // Source 2 marks a direct user action, which window managers
+            // honor despite focus stealing prevention
+            let event = ClientMessageEvent::new(
+                32,
+                window,
+                display.net_active_window,
+                [2, CURRENT_TIME, 0, 0, 0],
+            );
+            display
+                .conn
+                .send_event(
+                    false,
+                    display.root,
+                    EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+                    event,
+                )
+                .map_err(x11_error)?;
+            display.conn.flush().map_err(x11_error)?;
+        } else {
+            display
+                .conn
+                .set_input_focus(InputFocus::PARENT, window, CURRENT_TIME)
+                .map_err(x11_error)?
+                .check()
+                .map_err(x11_error)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use x11rb::protocol::xproto::{CreateWindowAux, PropMode, WindowClass};
+        use x11rb::wrapper::ConnectionExt as _;
+        use x11rb::COPY_DEPTH_FROM_PARENT;
+
+        /// A mapped window with `pid` as its `_NET_WM_PID`.
+        fn window(display: &Display, pid: u32) -> Window {
+            let screen = &display.conn.setup().roots[0];
+            let window = display.conn.generate_id().unwrap();
+            display
+                .conn
+                .create_window(
+                    COPY_DEPTH_FROM_PARENT,
+                    window,
+                    display.root,
+                    0,
+                    0,
+                    100,
+                    100,
+                    0,
+                    WindowClass::INPUT_OUTPUT,
+                    screen.root_visual,
+                    &CreateWindowAux::new(),
+                )
+                .unwrap();
+            display
+                .conn
+                .change_property32(
+                    PropMode::REPLACE,
+                    window,
+                    display.net_wm_pid,
+                    AtomEnum::CARDINAL,
+                    &[pid],
+                )
+                .unwrap();
+            display.conn.map_window(window).unwrap();
+            display.conn.sync().unwrap();
+            window
+        }
+
+        fn input_focus(display: &Display) -> Window {
+            display.conn.get_input_focus().unwrap().reply().unwrap().focus
+        }
+
+        #[test]
+        #[ignore = "needs an X server without a window manager, e.g. xvfb-run cargo test -- --ignored"]
+        fn focus_is_captured_and_restored() {
+            let display = Display::open().unwrap();
+            assert!(!display.has_ewmh().unwrap(), "run without a window manager");
+            let first = window(&display, 4242);
+            let second = window(&display, 4343);
+
+            display
+                .conn
+                .set_input_focus(InputFocus::PARENT, first, CURRENT_TIME)
+                .unwrap()
+                .check()
+                .unwrap();
+            let target = frontmost().unwrap();
+            assert_eq!(target, FocusTarget { pid: 4242, window: first });
+
+            activate(second).unwrap();
+            assert_eq!(input_focus(&display), second);
+            assert_eq!(frontmost().unwrap().pid, 4343);
+
+            activate(target.window).unwrap();
+            assert_eq!(input_focus(&display), first);
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn process_name_is_not_truncated() {
+        let exe = std::env::current_exe().unwrap();
+        let expected = exe.file_name().unwrap().to_str().unwrap();
+        assert_eq!(process_name(std::process::id() as i32).as_deref(), Some(expected));
+        assert_eq!(process_name(i32::MAX), None);
+    }
+}
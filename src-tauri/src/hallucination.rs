@@ -0,0 +1,321 @@
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config;
+
+/// Whisper settings for dropping text it invents on silence or noise:
+/// "Thanks for watching!", subtitle credits, or one phrase looping.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HallucinationFilter {
+    pub enabled: bool,
+    /// Drop segments Whisper itself rates as silence with a probability
+    /// above this...
+    pub max_no_speech_prob: f32,
+    /// ...if their average token log probability is also below this. Quiet
+    /// speech can look like silence yet be transcribed confidently.
+    pub min_avg_logprob: f32,
+    /// Drop segments whose text compresses better than this with zlib,
+    /// which happens when a phrase repeats over and over
+    pub max_compression_ratio: f32,
+    /// Phrases dropped when they make up a whole segment, on top of the
+    /// built-in ones. Case and punctuation are ignored.
+    pub blocklist: Vec<String>,
+    /// Append dropped segments, text included, to `hallucinations.jsonl`
+    pub log_dropped: bool,
+}
+
+impl Default for HallucinationFilter {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_no_speech_prob: 0.6,
+            min_avg_logprob: -1.0,
+            max_compression_ratio: 2.4,
+            blocklist: Vec::new(),
+            log_dropped: false,
+        }
+    }
+}
+
+/// Phrases Whisper learned from subtitled videos and produces when nobody
+/// speaks.
+const BUILTIN_BLOCKLIST: &[&str] = &[
+    "thanks for watching",
+    "thank you for watching",
+    "thank you so much for watching",
+    "thanks for watching and see you next time",
+    "please subscribe",
+    "like and subscribe",
+    "please like and subscribe",
+    "see you in the next video",
+    "subtitles by the amara.org community",
+    "sous-titres réalisés par la communauté d'amara.org",
+    "sous-titres réalisés para la communauté d'amara.org",
+    "sous-titres par la communauté d'amara.org",
+    "sous-titrage st' 501",
+    "sous-titrage société radio-canada",
+    "merci d'avoir regardé",
+    "merci d'avoir regardé cette vidéo",
+    "abonnez-vous",
+];
+
+/// A piece of a Whisper transcript.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    pub text: String,
+    pub no_speech_prob: f32,
+    /// Mean log probability of its text tokens
+    pub avg_logprob: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DropReason {
+    NoSpeech,
+    CompressionRatio,
+    Blocklist,
+    /// Same text as the segment before it
+    Repeated,
+}
+
+/// A segment [`filter`] left out, and why.
+#[derive(Debug, Clone)]
+pub struct Dropped {
+    pub segment: Segment,
+    pub reason: DropReason,
+    pub compression_ratio: f32,
+}
+
+/// One line of `hallucinations.jsonl`.
+#[derive(Serialize)]
+struct LogEntry<'a> {
+    /// Seconds since the Unix epoch
+    timestamp: u64,
+    reason: DropReason,
+    text: &'a str,
+    no_speech_prob: f32,
+    avg_logprob: f32,
+    compression_ratio: f32,
+}
+
+pub fn log_path() -> PathBuf {
+    config::state_dir().join("hallucinations.jsonl")
+}
+
+/// Comparable form of a segment: lower case, apostrophes straightened,
+/// punctuation around words dropped.
+fn normalize(text: &str) -> String {
+    text.to_lowercase()
+        .replace('’', "'")
+        .split_whitespace()
+        .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric() && c != '\''))
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Size of the text over the size of its zlib compression, as Whisper's
+/// reference implementation computes it.
+fn compression_ratio(text: &str) -> f32 {
+    let bytes = text.as_bytes();
+    if bytes.is_empty() {
+        return 0.0;
+    }
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    let compressed = encoder
+        .write_all(bytes)
+        .and_then(|_| encoder.finish())
+        .map(|c| c.len())
+        .unwrap_or(bytes.len());
+    bytes.len() as f32 / compressed.max(1) as f32
+}
+
+/// Split `segments` into those that look like real speech and those
+/// dropped as hallucinations.
+pub fn filter(segments: Vec<Segment>, settings: &HallucinationFilter) -> (Vec<Segment>, Vec<Dropped>) {
+    let blocklist: Vec<String> = BUILTIN_BLOCKLIST
+        .iter()
+        .map(|p| normalize(p))
+        .chain(settings.blocklist.iter().map(|p| normalize(p)))
+        .filter(|p| !p.is_empty())
+        .collect();
+
+    let mut kept: Vec<Segment> = Vec::new();
+    let mut dropped = Vec::new();
+    let mut previous = String::new();
+    for segment in segments {
+        let normalized = normalize(&segment.text);
+        if normalized.is_empty() {
+            continue;
+        }
+        let ratio = compression_ratio(segment.text.trim());
+
+        // Whisper's own rule: silence only when the words are doubtful too
+        let reason = if segment.no_speech_prob > settings.max_no_speech_prob
+            && segment.avg_logprob < settings.min_avg_logprob
+        {
+            Some(DropReason::NoSpeech)
+        } else if ratio > settings.max_compression_ratio {
+            Some(DropReason::CompressionRatio)
+        } else if blocklist.contains(&normalized) {
+            Some(DropReason::Blocklist)
+        } else if normalized == previous {
+            Some(DropReason::Repeated)
+        } else {
+            None
+        };
+
+        match reason {
+            Some(reason) => dropped.push(Dropped {
+                segment,
+                reason,
+                compression_ratio: ratio,
+            }),
+            None => {
+                previous = normalized;
+                kept.push(segment);
+            }
+        }
+    }
+    (kept, dropped)
+}
+
+/// Report `dropped` segments on stderr, without their text, and append them
+/// to `hallucinations.jsonl` if the settings ask for it.
+pub fn log(dropped: &[Dropped], settings: &HallucinationFilter) {
+    for d in dropped {
+        eprintln!(
+            "Dropped Whisper segment ({:?}, no_speech_prob {:.2}, avg_logprob {:.2}, compression ratio {:.2})",
+            d.reason, d.segment.no_speech_prob, d.segment.avg_logprob, d.compression_ratio
+        );
+    }
+    if settings.log_dropped && !dropped.is_empty() {
+        if let Err(e) = append_log(&log_path(), dropped) {
+            eprintln!("Failed to log dropped segments: {}", e);
+        }
+    }
+}
+
+fn append_log(path: &Path, dropped: &[Dropped]) -> std::io::Result<()> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    for d in dropped {
+        let entry = LogEntry {
+            timestamp,
+            reason: d.reason,
+            text: d.segment.text.trim(),
+            no_speech_prob: d.segment.no_speech_prob,
+            avg_logprob: d.segment.avg_logprob,
+            compression_ratio: d.compression_ratio,
+        };
+        let line = serde_json::to_string(&entry).map_err(std::io::Error::other)?;
+        writeln!(file, "{}", line)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(text: &str) -> Segment {
+        Segment {
+            text: text.to_string(),
+            no_speech_prob: 0.1,
+            avg_logprob: -0.3,
+        }
+    }
+
+    fn reasons(segments: Vec<Segment>, settings: &HallucinationFilter) -> Vec<(String, Option<DropReason>)> {
+        let (kept, dropped) = filter(segments, settings);
+        let mut out: Vec<_> = kept.into_iter().map(|s| (s.text, None)).collect();
+        out.extend(dropped.into_iter().map(|d| (d.segment.text, Some(d.reason))));
+        out
+    }
+
+    #[test]
+    fn silence_needs_doubtful_words() {
+        let settings = HallucinationFilter::default();
+        let quiet = Segment {
+            no_speech_prob: 0.8,
+            ..segment(" I said it quietly.")
+        };
+        let invented = Segment {
+            no_speech_prob: 0.8,
+            avg_logprob: -1.4,
+            ..segment(" Okay.")
+        };
+        assert_eq!(
+            reasons(vec![quiet, invented], &settings),
+            [
+                (" I said it quietly.".to_string(), None),
+                (" Okay.".to_string(), Some(DropReason::NoSpeech)),
+            ]
+        );
+    }
+
+    #[test]
+    fn looping_text_is_dropped() {
+        let looping = " the the the the the the the the the the the the the the the the the the";
+        let (kept, dropped) = filter(vec![segment(looping)], &HallucinationFilter::default());
+        assert!(kept.is_empty());
+        assert_eq!(dropped[0].reason, DropReason::CompressionRatio);
+        assert!(dropped[0].compression_ratio > 2.4);
+    }
+
+    #[test]
+    fn blocklisted_phrases_are_dropped_whole() {
+        let settings = HallucinationFilter {
+            blocklist: vec!["Merci.".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            reasons(
+                vec![
+                    segment(" Thanks for watching!"),
+                    segment(" Thanks for watching the demo."),
+                    segment(" merci"),
+                ],
+                &settings
+            ),
+            [
+                (" Thanks for watching the demo.".to_string(), None),
+                (" Thanks for watching!".to_string(), Some(DropReason::Blocklist)),
+                (" merci".to_string(), Some(DropReason::Blocklist)),
+            ]
+        );
+    }
+
+    #[test]
+    fn consecutive_repeats_are_dropped() {
+        let (kept, dropped) = filter(
+            vec![segment(" Hello there."), segment(" hello there"), segment(" Bye.")],
+            &HallucinationFilter::default(),
+        );
+        assert_eq!(kept, [segment(" Hello there."), segment(" Bye.")]);
+        assert_eq!(dropped[0].reason, DropReason::Repeated);
+    }
+
+    #[test]
+    fn log_file_is_written_only_on_request() {
+        assert!(!HallucinationFilter::default().log_dropped);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hallucinations.jsonl");
+        let (_, dropped) = filter(vec![segment(" Thanks for watching!")], &HallucinationFilter::default());
+        append_log(&path, &dropped).unwrap();
+        let line: serde_json::Value =
+            serde_json::from_str(std::fs::read_to_string(&path).unwrap().trim()).unwrap();
+        assert_eq!(line["reason"], "blocklist");
+        assert_eq!(line["text"], "Thanks for watching!");
+    }
+}
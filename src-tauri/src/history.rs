@@ -0,0 +1,187 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::{self, Engine, HistorySettings, Language};
+use crate::stt::TranscriptionResult;
+
+/// One line of `history.jsonl`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    pub text: String,
+    /// Engine that produced `text`
+    pub engine: Engine,
+    /// True when the fallback engine stepped in for the primary
+    pub fallback: bool,
+    pub language: Language,
+    /// Profile active when the dictation was made
+    #[serde(default)]
+    pub profile: String,
+}
+
+pub fn history_path() -> PathBuf {
+    config::data_dir().join("history.jsonl")
+}
+
+/// Append a transcription to the history file, unless history is turned
+/// off, and drop the entries beyond the configured limit.
+pub fn record(
+    result: &TranscriptionResult,
+    language: &Language,
+    profile: &str,
+    settings: &HistorySettings,
+) -> Result<(), String> {
+    record_at(&history_path(), result, language, profile, settings)
+}
+
+fn record_at(
+    path: &Path,
+    result: &TranscriptionResult,
+    language: &Language,
+    profile: &str,
+    settings: &HistorySettings,
+) -> Result<(), String> {
+    if !settings.enabled {
+        return Ok(());
+    }
+    let entry = HistoryEntry {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        text: result.text.clone(),
+        engine: result.engine,
+        fallback: result.fallback,
+        language: language.clone(),
+        profile: profile.to_string(),
+    };
+    append(path, &entry, settings.max_entries)
+}
+
+fn append(path: &Path, entry: &HistoryEntry, max_entries: usize) -> Result<(), String> {
+    let line = serde_json::to_string(entry).map_err(|e| format!("Failed to serialize: {}", e))?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open history: {}", e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to write history: {}", e))?;
+    drop(file);
+
+    if max_entries > 0 {
+        truncate(path, max_entries)?;
+    }
+    Ok(())
+}
+
+/// Keep only the last `max_entries` lines of the history file.
+fn truncate(path: &Path, max_entries: usize) -> Result<(), String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read history: {}", e))?;
+    let count = content.lines().count();
+    if count <= max_entries {
+        return Ok(());
+    }
+    let kept: String = content
+        .lines()
+        .skip(count - max_entries)
+        .map(|line| format!("{}\n", line))
+        .collect();
+    config::write_atomic(path, kept.as_bytes())
+}
+
+/// Most recent entries first, at most `limit` of them. Unreadable lines are skipped.
+pub fn load(limit: usize) -> Vec<HistoryEntry> {
+    load_from(&history_path(), limit)
+}
+
+fn load_from(path: &Path, limit: usize) -> Vec<HistoryEntry> {
+    let content = fs::read_to_string(path).unwrap_or_default();
+    content
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .take(limit)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(text: &str) -> HistoryEntry {
+        HistoryEntry {
+            timestamp: 1_700_000_000,
+            text: text.to_string(),
+            engine: Engine::Whisper,
+            fallback: false,
+            language: Language::try_from("en".to_string()).unwrap(),
+            profile: "Default".to_string(),
+        }
+    }
+
+    #[test]
+    fn entries_read_back_newest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        for text in ["one", "two", "three"] {
+            append(&path, &entry(text), 0).unwrap();
+        }
+        assert_eq!(load_from(&path, 10), [entry("three"), entry("two"), entry("one")]);
+        assert_eq!(load_from(&path, 1), [entry("three")]);
+    }
+
+    #[test]
+    fn oldest_entries_go_beyond_the_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        for text in ["one", "two", "three", "four"] {
+            append(&path, &entry(text), 2).unwrap();
+        }
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2);
+        assert_eq!(load_from(&path, 10), [entry("four"), entry("three")]);
+    }
+
+    #[test]
+    fn bad_lines_are_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        // Older entries have no profile
+        let old = r#"{"timestamp":1,"text":"old","engine":"parakeet","fallback":true,"language":"fr"}"#;
+        fs::write(&path, format!("{}\n{{ not json\n", old)).unwrap();
+        append(&path, &entry("new"), 0).unwrap();
+
+        let loaded = load_from(&path, 10);
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0], entry("new"));
+        assert_eq!(loaded[1].text, "old");
+        assert_eq!(loaded[1].profile, "");
+    }
+
+    #[test]
+    fn nothing_is_recorded_when_turned_off() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        let result = TranscriptionResult {
+            text: "secret".to_string(),
+            engine: Engine::Whisper,
+            fallback: false,
+        };
+        let language = Language::try_from("en".to_string()).unwrap();
+        let mut settings = HistorySettings {
+            enabled: false,
+            ..Default::default()
+        };
+
+        record_at(&path, &result, &language, "Default", &settings).unwrap();
+        assert!(!path.exists());
+
+        settings.enabled = true;
+        record_at(&path, &result, &language, "Default", &settings).unwrap();
+        assert_eq!(load_from(&path, 10)[0].text, "secret");
+    }
+}
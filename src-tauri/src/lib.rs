@@ -1,12 +1,28 @@
 mod audio;
+mod clipboard;
 mod commands;
 mod config;
+mod config_watcher;
+mod dictionary;
+mod engine_loader;
+mod focus;
+mod hallucination;
+mod history;
 mod model_manager;
+mod output;
 mod paste;
+mod postprocess;
 mod recording;
+mod shortcuts;
+mod snippets;
 mod state;
 mod stt;
+#[cfg(test)]
+mod test_server;
+mod text;
 mod tray;
+mod voice;
+mod wayland;
 
 use std::sync::Mutex;
 use tauri::Manager;
@@ -16,49 +32,37 @@ use state::AppState;
 pub fn run() {
     config::ensure_dirs();
 
-    let cfg = config::load_config();
+    let config::LoadedConfig { config: cfg, recovered } = config::load_config();
 
-    let mut engine = stt::SttEngine::from_engine_name(&cfg.engine);
-    let model_path = state::get_model_path_for_config(&cfg);
-    let has_model = if model_path.exists() {
-        match engine.load_model(&model_path) {
-            Ok(()) => {
-                println!("STT engine [{}] loaded: {}", cfg.engine, model_path.display());
-                true
-            }
-            Err(e) => {
-                eprintln!("Failed to load STT engine: {}", e);
-                false
-            }
-        }
-    } else {
-        println!("No model found for engine '{}'. Please download via Settings.", cfg.engine);
-        false
-    };
+    // The model itself is loaded in the background once the app is up
+    let engine = stt::SttEngine::new(cfg.active().engine);
+    let has_model =
+        model_manager::model_exists_for_engine(cfg.active().engine, cfg.active().model_size);
+    if !has_model {
+        eprintln!(
+            "No model found for engine '{}'. Please download via Settings.",
+            cfg.active().engine
+        );
+    }
 
     let app_state = AppState {
         recorder: Mutex::new(audio::AudioRecorder::new()),
         engine: Mutex::new(engine),
-        config: Mutex::new(cfg),
-        previous_app_pid: std::sync::atomic::AtomicI32::new(-1),
+        fallback_engine: Mutex::new(None),
+        config: Mutex::new(cfg.clone()),
+        previous_focus: Mutex::new(focus::FocusTarget::default()),
+        engine_loading: std::sync::atomic::AtomicBool::new(false),
+        engine_generation: std::sync::atomic::AtomicU64::new(0),
+        dictation: Mutex::new(recording::DictationMode::default()),
+        last_transcript: Mutex::new(None),
+        last_paste: Mutex::new(None),
+        startup_notices: Mutex::new(Vec::new()),
     };
 
     tauri::Builder::default()
         .plugin(
             tauri_plugin_global_shortcut::Builder::new()
-                .with_handler(|app, shortcut, event| {
-                    if event.state == tauri_plugin_global_shortcut::ShortcutState::Pressed {
-                        let app = app.clone();
-                        let is_escape = shortcut.key == tauri_plugin_global_shortcut::Code::Escape;
-                        std::thread::spawn(move || {
-                            if is_escape {
-                                recording::cancel_recording(&app);
-                            } else {
-                                recording::do_toggle_recording(&app);
-                            }
-                        });
-                    }
-                })
+                .with_handler(shortcuts::handle)
                 .build(),
         )
         .manage(app_state)
@@ -68,7 +72,24 @@ pub fn run() {
             commands::list_audio_devices,
             commands::check_model_exists,
             commands::download_model,
-            commands::change_shortcut,
+            commands::set_bindings,
+            commands::get_engine_status,
+            commands::get_history,
+            commands::switch_profile,
+            commands::preview_postprocess,
+            commands::list_dictionary,
+            commands::add_dictionary_rule,
+            commands::update_dictionary_rule,
+            commands::delete_dictionary_rule,
+            commands::import_dictionary,
+            commands::export_dictionary,
+            commands::undo_last_dictation,
+            commands::list_snippets,
+            commands::add_snippet,
+            commands::update_snippet,
+            commands::delete_snippet,
+            commands::wayland_diagnostics,
+            commands::take_startup_notices,
         ])
         .on_window_event(|window, event| {
             if window.label() == "settings" {
@@ -89,17 +110,39 @@ pub fn run() {
 
             tray::setup_tray(app.handle())?;
 
-            use tauri_plugin_global_shortcut::GlobalShortcutExt;
-            let shortcut = app.state::<AppState>().config.lock().unwrap().shortcut.clone();
-            app.global_shortcut().register(shortcut.as_str())?;
-
-            if !has_model {
+            // The settings page is not listening yet, so problems are kept
+            // until it fetches them
+            let mut notices: Vec<String> = recovered.into_iter().collect();
+            // Missing Wayland tools would otherwise only show on the first paste
+            notices.extend(wayland::diagnose().problems);
+            if let Err(e) = shortcuts::register_bindings(app.handle(), &cfg.bindings) {
+                notices.push(e.message);
+            }
+            for notice in &notices {
+                eprintln!("ERROR: {}", notice);
+            }
+            if !notices.is_empty() {
                 if let Some(window) = app.get_webview_window("settings") {
                     let _ = window.show();
                     let _ = window.set_focus();
-                    let _ = window.center();
                 }
             }
+            *app.state::<AppState>().startup_notices.lock().unwrap() = notices;
+
+            if let Err(e) = config_watcher::start(app.handle()) {
+                eprintln!("{}", e);
+            }
+
+            engine_loader::spawn_fallback_load(app.handle(), &cfg);
+
+            if has_model {
+                let profile = cfg.active();
+                engine_loader::spawn_load(app.handle(), profile.engine, profile.model_size);
+            } else if let Some(window) = app.get_webview_window("settings") {
+                let _ = window.show();
+                let _ = window.set_focus();
+                let _ = window.center();
+            }
 
             Ok(())
         })
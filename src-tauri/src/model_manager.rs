@@ -1,4 +1,4 @@
-use crate::config;
+use crate::config::{self, Engine, ModelSize};
 use futures_util::StreamExt;
 use std::path::PathBuf;
 use tauri::{AppHandle, Emitter};
@@ -12,21 +12,15 @@ const PARAKEET_BASE_URL: &str =
 
 // ── Whisper helpers ──
 
-pub fn whisper_model_filename(model_size: &str) -> String {
-    match model_size {
-        "tiny" => "ggml-tiny.bin".to_string(),
-        "base" => "ggml-base.bin".to_string(),
-        "small" => "ggml-small.bin".to_string(),
-        "medium" => "ggml-medium.bin".to_string(),
-        _ => "ggml-base.bin".to_string(),
-    }
+pub fn whisper_model_filename(model_size: ModelSize) -> String {
+    format!("ggml-{}.bin", model_size.as_str())
 }
 
-pub fn whisper_model_path(model_size: &str) -> PathBuf {
+pub fn whisper_model_path(model_size: ModelSize) -> PathBuf {
     config::models_dir().join(whisper_model_filename(model_size))
 }
 
-pub fn whisper_model_exists(model_size: &str) -> bool {
+pub fn whisper_model_exists(model_size: ModelSize) -> bool {
     whisper_model_path(model_size).exists()
 }
 
@@ -52,10 +46,11 @@ pub fn parakeet_model_exists() -> bool {
 
 // ── Unified check ──
 
-pub fn model_exists_for_engine(engine: &str, model_size: &str) -> bool {
+pub fn model_exists_for_engine(engine: Engine, model_size: ModelSize) -> bool {
     match engine {
-        "parakeet" => parakeet_model_exists(),
-        _ => whisper_model_exists(model_size),
+        Engine::Parakeet => parakeet_model_exists(),
+        Engine::Whisper => whisper_model_exists(model_size),
+        Engine::Remote => true,
     }
 }
 
@@ -127,7 +122,7 @@ async fn download_file(
 
 // ── Public download functions ──
 
-pub async fn download_whisper_model(model_size: &str, app: AppHandle) -> Result<PathBuf, String> {
+pub async fn download_whisper_model(model_size: ModelSize, app: AppHandle) -> Result<PathBuf, String> {
     let filename = whisper_model_filename(model_size);
     let url = format!("{}/{}", WHISPER_BASE_URL, filename);
     let dest = whisper_model_path(model_size);
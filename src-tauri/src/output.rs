@@ -0,0 +1,377 @@
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::config::{self, AppRule};
+use crate::paste;
+
+/// Where dictated text goes. Several outputs can be enabled at once.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Output {
+    /// Put the text on the clipboard and press Cmd+V / Ctrl+V
+    Paste,
+    /// Type the text as simulated key presses
+    Type,
+    /// Put the text on the clipboard without pasting it
+    Clipboard,
+    /// Append the text to a file, one timestamped line per dictation
+    File { path: String },
+    /// Print the text on standard output, one line per dictation
+    Stdout,
+    /// Write the text to a named pipe, one line per dictation. Fails when no
+    /// program is reading the pipe.
+    Fifo { path: String },
+}
+
+impl Output {
+    /// Human-readable description, for error messages.
+    pub fn label(&self) -> String {
+        match self {
+            Output::Paste => "paste".to_string(),
+            Output::Type => "type".to_string(),
+            Output::Clipboard => "clipboard".to_string(),
+            Output::File { path } => format!("file `{}`", path),
+            Output::Stdout => "stdout".to_string(),
+            Output::Fifo { path } => format!("FIFO `{}`", path),
+        }
+    }
+
+    pub fn sink(&self) -> Box<dyn OutputSink> {
+        match self {
+            Output::Paste => Box::new(PasteSink { chord: None }),
+            Output::Type => Box::new(TypeSink),
+            Output::Clipboard => Box::new(ClipboardSink),
+            Output::File { path } => Box::new(FileSink {
+                path: config::expand_home(path),
+            }),
+            Output::Stdout => Box::new(StdoutSink),
+            Output::Fifo { path } => Box::new(FifoSink {
+                path: config::expand_home(path),
+            }),
+        }
+    }
+}
+
+pub fn default_outputs() -> Vec<Output> {
+    vec![Output::Paste]
+}
+
+/// Sinks for `outputs`, adjusted by the rule of the app the text goes to.
+pub fn sinks(outputs: &[Output], rule: Option<&AppRule>) -> Vec<Box<dyn OutputSink>> {
+    outputs
+        .iter()
+        .map(|output| match (output, rule) {
+            (Output::Paste, Some(rule)) if rule.type_instead_of_paste => {
+                Box::new(TypeSink) as Box<dyn OutputSink>
+            }
+            (Output::Paste, Some(rule)) => Box::new(PasteSink {
+                chord: rule.paste_chord.clone(),
+            }),
+            _ => output.sink(),
+        })
+        .collect()
+}
+
+/// A destination for dictated text.
+pub trait OutputSink {
+    fn label(&self) -> String;
+
+    /// Deliver one dictation.
+    fn write(&mut self, text: &str) -> Result<(), String>;
+
+    /// Whether the text lands in the focused app, which then needs its focus
+    /// back before writing and can have the text undone afterwards.
+    fn inserts_text(&self) -> bool {
+        false
+    }
+}
+
+pub struct PasteSink {
+    /// Shortcut pressed instead of Cmd+V / Ctrl+V
+    pub chord: Option<String>,
+}
+
+impl OutputSink for PasteSink {
+    fn label(&self) -> String {
+        Output::Paste.label()
+    }
+
+    fn write(&mut self, text: &str) -> Result<(), String> {
+        paste::paste_text(text, self.chord.as_deref())
+    }
+
+    fn inserts_text(&self) -> bool {
+        true
+    }
+}
+
+pub struct TypeSink;
+
+impl OutputSink for TypeSink {
+    fn label(&self) -> String {
+        Output::Type.label()
+    }
+
+    fn write(&mut self, text: &str) -> Result<(), String> {
+        paste::type_text(text)
+    }
+
+    fn inserts_text(&self) -> bool {
+        true
+    }
+}
+
+pub struct ClipboardSink;
+
+impl OutputSink for ClipboardSink {
+    fn label(&self) -> String {
+        Output::Clipboard.label()
+    }
+
+    fn write(&mut self, text: &str) -> Result<(), String> {
+        paste::copy_text(text)
+    }
+}
+
+pub struct FileSink {
+    pub path: PathBuf,
+}
+
+impl OutputSink for FileSink {
+    fn label(&self) -> String {
+        format!("file `{}`", self.path.display())
+    }
+
+    fn write(&mut self, text: &str) -> Result<(), String> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("Failed to open {}: {}", self.path.display(), e))?;
+        let line = format!(
+            "[{}] {}\n",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+            text
+        );
+        file.write_all(line.as_bytes())
+            .map_err(|e| format!("Failed to write {}: {}", self.path.display(), e))
+    }
+}
+
+pub struct StdoutSink;
+
+impl OutputSink for StdoutSink {
+    fn label(&self) -> String {
+        Output::Stdout.label()
+    }
+
+    fn write(&mut self, text: &str) -> Result<(), String> {
+        write_line(&mut std::io::stdout().lock(), text)
+            .map_err(|e| format!("Failed to write to stdout: {}", e))
+    }
+}
+
+/// One dictation as a line, and nothing else: programs reading stdout take
+/// each line for a transcript.
+fn write_line(out: &mut impl Write, text: &str) -> std::io::Result<()> {
+    writeln!(out, "{}", text)?;
+    out.flush()
+}
+
+pub struct FifoSink {
+    pub path: PathBuf,
+}
+
+impl OutputSink for FifoSink {
+    fn label(&self) -> String {
+        format!("FIFO `{}`", self.path.display())
+    }
+
+    #[cfg(unix)]
+    fn write(&mut self, text: &str) -> Result<(), String> {
+        use std::os::unix::fs::{FileTypeExt, OpenOptionsExt};
+
+        let is_fifo = std::fs::metadata(&self.path)
+            .map(|m| m.file_type().is_fifo())
+            .map_err(|e| format!("Failed to open {}: {}", self.path.display(), e))?;
+        if !is_fifo {
+            return Err(format!("{} is not a named pipe", self.path.display()));
+        }
+        // Without O_NONBLOCK, opening a pipe nobody reads blocks forever
+        let mut fifo = OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(&self.path)
+            .map_err(|e| match e.raw_os_error() {
+                Some(libc::ENXIO) => format!("Nothing is reading {}", self.path.display()),
+                _ => format!("Failed to open {}: {}", self.path.display(), e),
+            })?;
+        fifo.write_all(format!("{}\n", text).as_bytes())
+            .map_err(|e| format!("Failed to write {}: {}", self.path.display(), e))
+    }
+
+    #[cfg(not(unix))]
+    fn write(&mut self, _text: &str) -> Result<(), String> {
+        Err("Named pipes are only supported on macOS and Linux".to_string())
+    }
+}
+
+/// Result of sending a dictation to every sink.
+pub struct Delivery {
+    /// Some sink put the text into the focused app
+    pub inserted: bool,
+    /// One message per sink that failed
+    pub errors: Vec<String>,
+}
+
+/// Send `text` to each sink in turn. A failing sink does not stop the
+/// others.
+pub fn write_all(sinks: &mut [Box<dyn OutputSink>], text: &str) -> Delivery {
+    let mut delivery = Delivery {
+        inserted: false,
+        errors: Vec::new(),
+    };
+    for sink in sinks.iter_mut() {
+        match sink.write(text) {
+            Ok(()) => delivery.inserted |= sink.inserts_text(),
+            Err(e) => delivery.errors.push(format!("{}: {}", sink.label(), e)),
+        }
+    }
+    delivery
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// Keeps everything written to it, to check what a dictation produced
+    /// without touching the desktop.
+    #[derive(Default)]
+    struct RecordingSink {
+        written: Arc<Mutex<Vec<String>>>,
+        inserts: bool,
+        /// Fail every write with this message
+        fail: Option<&'static str>,
+    }
+
+    impl OutputSink for RecordingSink {
+        fn label(&self) -> String {
+            "recording".to_string()
+        }
+
+        fn write(&mut self, text: &str) -> Result<(), String> {
+            if let Some(e) = self.fail {
+                return Err(e.to_string());
+            }
+            self.written.lock().unwrap().push(text.to_string());
+            Ok(())
+        }
+
+        fn inserts_text(&self) -> bool {
+            self.inserts
+        }
+    }
+
+    #[test]
+    fn failing_sink_does_not_stop_the_others() {
+        let written = Arc::new(Mutex::new(Vec::new()));
+        let mut sinks: Vec<Box<dyn OutputSink>> = vec![
+            Box::new(RecordingSink {
+                fail: Some("busy"),
+                inserts: true,
+                ..Default::default()
+            }),
+            Box::new(RecordingSink {
+                written: written.clone(),
+                ..Default::default()
+            }),
+            Box::new(RecordingSink {
+                fail: Some("gone"),
+                ..Default::default()
+            }),
+        ];
+        let delivery = write_all(&mut sinks, "hello");
+        assert_eq!(*written.lock().unwrap(), ["hello"]);
+        assert_eq!(delivery.errors, ["recording: busy", "recording: gone"]);
+        // The sink that inserts text failed
+        assert!(!delivery.inserted);
+    }
+
+    #[test]
+    fn inserted_when_an_inserting_sink_succeeds() {
+        let mut sinks: Vec<Box<dyn OutputSink>> = vec![
+            Box::new(RecordingSink::default()),
+            Box::new(RecordingSink {
+                inserts: true,
+                ..Default::default()
+            }),
+        ];
+        let delivery = write_all(&mut sinks, "hello");
+        assert!(delivery.inserted);
+        assert!(delivery.errors.is_empty());
+
+        let mut sinks: Vec<Box<dyn OutputSink>> = vec![Box::new(RecordingSink::default())];
+        assert!(!write_all(&mut sinks, "hello").inserted);
+    }
+
+    #[test]
+    fn app_rule_replaces_paste() {
+        let outputs = [Output::Paste, Output::Clipboard];
+        let typing = AppRule {
+            type_instead_of_paste: true,
+            ..Default::default()
+        };
+        let labels = |sinks: Vec<Box<dyn OutputSink>>| -> Vec<String> {
+            sinks.iter().map(|s| s.label()).collect()
+        };
+        assert_eq!(labels(sinks(&outputs, None)), ["paste", "clipboard"]);
+        assert_eq!(labels(sinks(&outputs, Some(&typing))), ["type", "clipboard"]);
+    }
+
+    #[test]
+    fn stdout_holds_only_the_transcript() {
+        let mut out = Vec::new();
+        write_line(&mut out, "Hello, world.").unwrap();
+        write_line(&mut out, "second").unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "Hello, world.\nsecond\n");
+    }
+
+    #[test]
+    fn file_sink_appends_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes").join("dictation.log");
+        let mut sink = FileSink { path: path.clone() };
+        sink.write("first").unwrap();
+        sink.write("second").unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with('[') && lines[0].ends_with("] first"));
+        assert!(lines[1].ends_with("] second"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn fifo_sink_needs_a_reader() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pipe");
+        let mut sink = FifoSink { path: path.clone() };
+        assert!(sink.write("x").unwrap_err().starts_with("Failed to open"));
+
+        std::fs::write(&path, "").unwrap();
+        assert!(sink.write("x").unwrap_err().ends_with("is not a named pipe"));
+
+        std::fs::remove_file(&path).unwrap();
+        let c_path = std::ffi::CString::new(path.to_str().unwrap()).unwrap();
+        assert_eq!(unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) }, 0);
+        assert!(sink.write("x").unwrap_err().starts_with("Nothing is reading"));
+    }
+}
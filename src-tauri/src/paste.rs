@@ -1,94 +1,154 @@
 use arboard::Clipboard;
 
-// ── macOS focus management via ObjC runtime (no extra deps, no permissions needed) ──
-
-#[cfg(target_os = "macos")]
-mod macos_focus {
-    use std::ffi::c_void;
-
-    extern "C" {
-        fn objc_getClass(name: *const u8) -> *mut c_void;
-        fn sel_registerName(name: *const u8) -> *mut c_void;
-        fn objc_msgSend();
-    }
-
-    /// Get the PID of the currently frontmost application.
-    pub fn get_frontmost_pid() -> i32 {
-        unsafe {
-            type SendObj = unsafe extern "C" fn(*mut c_void, *mut c_void) -> *mut c_void;
-            type SendI32 = unsafe extern "C" fn(*mut c_void, *mut c_void) -> i32;
-            let send_obj: SendObj = std::mem::transmute(objc_msgSend as *const ());
-            let send_i32: SendI32 = std::mem::transmute(objc_msgSend as *const ());
-
-            let cls = objc_getClass(b"NSWorkspace\0".as_ptr());
-            let workspace = send_obj(cls, sel_registerName(b"sharedWorkspace\0".as_ptr()));
-            let app = send_obj(
-                workspace,
-                sel_registerName(b"frontmostApplication\0".as_ptr()),
-            );
-            if app.is_null() {
-                return -1;
-            }
-            send_i32(app, sel_registerName(b"processIdentifier\0".as_ptr()))
-        }
+use crate::clipboard;
+use crate::wayland;
+
+/// Paste `text` with Cmd+V / Ctrl+V, or with `chord` in apps that paste
+/// with another shortcut, e.g. `"Ctrl+Shift+V"` in terminals.
+pub fn paste_text(text: &str, chord: Option<&str>) -> Result<(), String> {
+    let press = || match chord {
+        Some(chord) => press_chord(chord),
+        None => simulate_paste(),
+    };
+    let mut clipboard = match Clipboard::new() {
+        Ok(clipboard) => clipboard,
+        Err(_) if wayland::is_session() => return clipboard::paste_text_wl(text, &press),
+        Err(e) => return Err(format!("Clipboard init error: {}", e)),
+    };
+
+    // Save everything the user had copied (best effort)
+    let previous = clipboard::Contents::read(&mut clipboard);
+
+    // Simulate the paste shortcut once the text is on the clipboard
+    let pasted = clipboard::paste_text(&mut clipboard, text, &press);
+
+    if let Err(e) = previous.write() {
+        eprintln!("Failed to restore the clipboard: {}", e);
     }
+    pasted
+}
 
-    /// Bring an application to the foreground by its PID.
-    pub fn activate_pid(pid: i32) {
-        unsafe {
-            type SendWithI32 = unsafe extern "C" fn(*mut c_void, *mut c_void, i32) -> *mut c_void;
-            type SendWithUsize =
-                unsafe extern "C" fn(*mut c_void, *mut c_void, usize) -> bool;
-            let send_with_i32: SendWithI32 = std::mem::transmute(objc_msgSend as *const ());
-            let send_with_usize: SendWithUsize = std::mem::transmute(objc_msgSend as *const ());
-
-            let cls = objc_getClass(b"NSRunningApplication\0".as_ptr());
-            let app = send_with_i32(
-                cls,
-                sel_registerName(b"runningApplicationWithProcessIdentifier:\0".as_ptr()),
-                pid,
-            );
-            if !app.is_null() {
-                // NSApplicationActivateIgnoringOtherApps = 2
-                send_with_usize(app, sel_registerName(b"activateWithOptions:\0".as_ptr()), 2);
-            }
-        }
-    }
+/// Put `text` on the clipboard without pasting it.
+pub fn copy_text(text: &str) -> Result<(), String> {
+    clipboard::Contents::text(text).write()
 }
 
-#[cfg(target_os = "macos")]
-pub use macos_focus::{activate_pid, get_frontmost_pid};
+/// Type `text` as simulated key presses. Slower than pasting, but works in
+/// apps that block paste and leaves the clipboard alone.
+pub fn type_text(text: &str) -> Result<(), String> {
+    use enigo::{Enigo, Keyboard, Settings};
 
-#[cfg(not(target_os = "macos"))]
-pub fn get_frontmost_pid() -> i32 { -1 }
+    if wayland::is_session() {
+        return wayland::type_text(text);
+    }
+    let mut enigo =
+        Enigo::new(&Settings::default()).map_err(|e| format!("Enigo init error: {}", e))?;
+    enigo
+        .text(text)
+        .map_err(|e| format!("Typing error: {}", e))
+}
 
-#[cfg(not(target_os = "macos"))]
-pub fn activate_pid(_pid: i32) {}
+/// Keys of a chord such as `"Ctrl+Shift+Z"` or `"Enter"`, in the order they
+/// are pressed. `CmdOrCtrl` is Cmd on macOS and Ctrl elsewhere.
+fn parse_chord(chord: &str) -> Result<Vec<enigo::Key>, String> {
+    use enigo::Key;
+
+    const F_KEYS: [Key; 12] = [
+        Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6,
+        Key::F7, Key::F8, Key::F9, Key::F10, Key::F11, Key::F12,
+    ];
+
+    chord
+        .split('+')
+        .map(|part| {
+            let name = part.trim().to_lowercase();
+            let key = match name.as_str() {
+                "ctrl" | "control" => Key::Control,
+                "shift" => Key::Shift,
+                "alt" | "option" => Key::Alt,
+                "cmd" | "command" | "super" | "meta" | "win" => Key::Meta,
+                "cmdorctrl" if cfg!(target_os = "macos") => Key::Meta,
+                "cmdorctrl" => Key::Control,
+                "enter" | "return" => Key::Return,
+                "tab" => Key::Tab,
+                "escape" | "esc" => Key::Escape,
+                "backspace" => Key::Backspace,
+                "delete" | "del" => Key::Delete,
+                "space" => Key::Space,
+                "up" => Key::UpArrow,
+                "down" => Key::DownArrow,
+                "left" => Key::LeftArrow,
+                "right" => Key::RightArrow,
+                "home" => Key::Home,
+                "end" => Key::End,
+                "pageup" => Key::PageUp,
+                "pagedown" => Key::PageDown,
+                _ => {
+                    let f_key = name
+                        .strip_prefix('f')
+                        .and_then(|n| n.parse::<usize>().ok())
+                        .and_then(|n| F_KEYS.get(n.wrapping_sub(1)));
+                    let mut chars = name.chars();
+                    match (f_key, chars.next(), chars.next()) {
+                        (Some(key), _, _) => *key,
+                        (None, Some(c), None) => Key::Unicode(c),
+                        _ => return Err(format!("Unknown key `{}` in `{}`", part.trim(), chord)),
+                    }
+                }
+            };
+            Ok(key)
+        })
+        .collect()
+}
 
-pub fn paste_text(text: &str) -> Result<(), String> {
-    // Save current clipboard content (best effort)
-    let mut clipboard =
-        Clipboard::new().map_err(|e| format!("Clipboard init error: {}", e))?;
+/// Check that `chord` names keys [`press_chord`] can press.
+pub fn check_chord(chord: &str) -> Result<(), String> {
+    parse_chord(chord).map(|_| ())
+}
 
-    let previous = clipboard.get_text().ok();
+/// Hold the modifiers of `chord`, tap its last key, then release them.
+pub fn press_chord(chord: &str) -> Result<(), String> {
+    use enigo::{Direction, Enigo, Keyboard, Settings};
 
-    // Set new text
-    clipboard
-        .set_text(text)
-        .map_err(|e| format!("Clipboard set error: {}", e))?;
+    let keys = parse_chord(chord)?;
+    if wayland::is_session() {
+        return wayland::press_chord(&keys, 1);
+    }
+    let Some((last, modifiers)) = keys.split_last() else {
+        return Ok(());
+    };
+    let mut enigo =
+        Enigo::new(&Settings::default()).map_err(|e| format!("Enigo init error: {}", e))?;
 
-    // Small delay to ensure clipboard is ready
-    std::thread::sleep(std::time::Duration::from_millis(50));
+    for key in modifiers {
+        enigo
+            .key(*key, Direction::Press)
+            .map_err(|e| format!("Key press error: {}", e))?;
+    }
+    let clicked = enigo
+        .key(*last, Direction::Click)
+        .map_err(|e| format!("Key click error: {}", e));
+    // Release even if the click failed, or the modifiers stay stuck
+    for key in modifiers.iter().rev() {
+        let _ = enigo.key(*key, Direction::Release);
+    }
+    clicked
+}
 
-    // Simulate Cmd+V / Ctrl+V
-    simulate_paste()?;
+/// Tap Backspace `count` times.
+pub fn press_backspaces(count: usize) -> Result<(), String> {
+    use enigo::{Direction, Enigo, Key, Keyboard, Settings};
 
-    // Small delay then restore clipboard (best effort)
-    std::thread::sleep(std::time::Duration::from_millis(100));
-    if let Some(prev) = previous {
-        let _ = clipboard.set_text(&prev);
+    if wayland::is_session() {
+        return wayland::press_chord(&[Key::Backspace], count);
+    }
+    let mut enigo =
+        Enigo::new(&Settings::default()).map_err(|e| format!("Enigo init error: {}", e))?;
+    for _ in 0..count {
+        enigo
+            .key(Key::Backspace, Direction::Click)
+            .map_err(|e| format!("Key click error: {}", e))?;
     }
-
     Ok(())
 }
 
@@ -194,6 +254,9 @@ fn simulate_paste() -> Result<(), String> {
 fn simulate_paste() -> Result<(), String> {
     use enigo::{Direction, Enigo, Key, Keyboard, Settings};
 
+    if wayland::is_session() {
+        return wayland::press_chord(&[Key::Control, Key::Unicode('v')], 1);
+    }
     let mut enigo =
         Enigo::new(&Settings::default()).map_err(|e| format!("Enigo init error: {}", e))?;
 
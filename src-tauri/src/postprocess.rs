@@ -0,0 +1,150 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::Language;
+
+mod fillers;
+mod itn;
+mod llm;
+mod punctuation;
+
+pub use fillers::FillerWord;
+pub use llm::LlmEdit;
+pub use punctuation::{Join, PunctuationRule};
+
+/// What the transforms know about the dictation being processed.
+pub struct Context<'a> {
+    pub language: &'a Language,
+    /// Name of the active profile
+    pub profile: &'a str,
+}
+
+/// One rule-based step applied to the transcribed text before it is pasted.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Transform {
+    /// Collapse runs of whitespace into one space and trim both ends
+    NormalizeWhitespace,
+    /// Upper-case the first letter
+    CapitalizeFirst,
+    /// Drop one trailing period, e.g. when dictating into search boxes.
+    /// Whitespace after it stays, such as a suffix added by `Wrap`.
+    StripTrailingPeriod,
+    /// Change the case of the whole text
+    Case { case: TextCase },
+    /// Write spoken numbers, decimals, percentages, currencies, units and
+    /// dates as figures: "twenty three percent" → "23%"
+    InverseTextNormalization,
+    /// Strip hesitations ("um", "euh") and, if `collapse_repeats` is set,
+    /// stutters ("the the", "th- the"). `words` adds to the built-in lists.
+    RemoveFillers {
+        #[serde(default)]
+        words: Vec<FillerWord>,
+        #[serde(default = "default_true")]
+        collapse_repeats: bool,
+    },
+    /// Turn phrases like "new line" or "virgule" into the characters they
+    /// name. `rules` extend and override the built-in phrases.
+    SpokenPunctuation {
+        #[serde(default)]
+        rules: Vec<PunctuationRule>,
+    },
+    /// Send the text to a local or remote LLM with a prompt, e.g. to fix
+    /// grammar or format it as a list. Keeps the text as is on failure.
+    LlmEdit(LlmEdit),
+    /// Surround the text, e.g. with a trailing space so consecutive
+    /// dictations don't run together
+    Wrap {
+        #[serde(default)]
+        prefix: String,
+        #[serde(default)]
+        suffix: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextCase {
+    Lower,
+    Upper,
+}
+
+/// A transform in a profile's chain. Disabled steps keep their settings but
+/// are skipped.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransformConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(flatten)]
+    pub transform: Transform,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Transform {
+    pub fn apply(&self, text: &str, ctx: &Context) -> String {
+        match self {
+            Transform::NormalizeWhitespace => text.split_whitespace().collect::<Vec<_>>().join(" "),
+            Transform::CapitalizeFirst => {
+                let mut chars = text.chars();
+                match chars.next() {
+                    Some(first) => first.to_uppercase().chain(chars).collect(),
+                    None => String::new(),
+                }
+            }
+            Transform::StripTrailingPeriod => {
+                let trimmed = text.trim_end();
+                // Keep ellipses intact
+                match trimmed.strip_suffix('.') {
+                    Some(rest) if !rest.ends_with('.') => {
+                        format!("{}{}", rest, &text[trimmed.len()..])
+                    }
+                    _ => text.to_string(),
+                }
+            }
+            Transform::Case { case } => match case {
+                TextCase::Lower => text.to_lowercase(),
+                TextCase::Upper => text.to_uppercase(),
+            },
+            Transform::InverseTextNormalization => itn::apply(text, ctx.language),
+            Transform::RemoveFillers {
+                words,
+                collapse_repeats,
+            } => fillers::apply(text, words, *collapse_repeats, ctx.language),
+            Transform::SpokenPunctuation { rules } => punctuation::apply(text, rules, ctx.language),
+            Transform::LlmEdit(llm) => llm.apply(text),
+            Transform::Wrap { prefix, suffix } => format!("{}{}{}", prefix, text, suffix),
+        }
+    }
+}
+
+/// Run `text` through the enabled transforms of `chain`, in order.
+pub fn run(chain: &[TransformConfig], text: &str, ctx: &Context) -> String {
+    chain
+        .iter()
+        .filter(|t| t.enabled)
+        .fold(text.to_string(), |text, t| t.transform.apply(&text, ctx))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip(text: &str) -> String {
+        let ctx = Context {
+            language: &Language::auto(),
+            profile: "Default",
+        };
+        Transform::StripTrailingPeriod.apply(text, &ctx)
+    }
+
+    #[test]
+    fn strip_trailing_period_keeps_whitespace_and_ellipses() {
+        assert_eq!(strip("Hello world."), "Hello world");
+        assert_eq!(strip("Hello world. "), "Hello world ");
+        assert_eq!(strip("- [ ] Buy milk.\n"), "- [ ] Buy milk\n");
+        assert_eq!(strip("Wait..."), "Wait...");
+        assert_eq!(strip("Done"), "Done");
+    }
+}
@@ -0,0 +1,246 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::Language;
+
+/// An extra hesitation token to strip.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FillerWord {
+    pub word: String,
+    /// Only stripped when dictating in this language; `None` for all
+    #[serde(default)]
+    pub language: Option<Language>,
+}
+
+/// Built-in hesitation tokens per language code.
+fn builtin(code: &str) -> &'static [&'static str] {
+    match code {
+        "en" => &["um", "umm", "uh", "er", "erm", "uhm", "hm", "hmm", "mm"],
+        "fr" => &["euh", "heu", "ben", "bah", "hum"],
+        _ => &[],
+    }
+}
+
+/// Words that are legitimately doubled ("I know that that works",
+/// "nous nous sommes vus", "so so good") and must survive repeat
+/// collapsing.
+fn allowed_repeats(code: &str) -> &'static [&'static str] {
+    match code {
+        "en" => &["that", "had", "is", "so", "very", "really", "no"],
+        "fr" => &["nous", "vous", "très", "non"],
+        _ => &[],
+    }
+}
+
+/// Hesitation tokens that are also first names: capitalised, they are only
+/// stripped when they open a sentence and the engine set them off with
+/// punctuation ("Ben, je sais pas").
+const NAMES: &[&str] = &["ben"];
+
+const BUILTIN_LANGUAGES: &[&str] = &["en", "fr"];
+
+const SENTENCE_END: &[char] = &['.', '!', '?'];
+const PUNCTUATION: &[char] = &[',', '.', ';', ':', '!', '?', '…'];
+
+/// Lower case without surrounding punctuation.
+fn core(word: &str) -> String {
+    word.trim_matches(PUNCTUATION).to_lowercase()
+}
+
+/// Letters of `word` with the length of each run, e.g. "umm" is
+/// `[('u', 1), ('m', 2)]`.
+fn runs(word: &str) -> Vec<(char, usize)> {
+    let mut out: Vec<(char, usize)> = Vec::new();
+    for c in word.chars() {
+        match out.last_mut() {
+            Some((last, n)) if *last == c => *n += 1,
+            _ => out.push((c, 1)),
+        }
+    }
+    out
+}
+
+/// Whether `word` spells `filler`, allowing a drawn-out letter: "ummm" and
+/// "euhhh" match "um" and "euh", but "err" is not "er".
+fn spells(word: &str, filler: &str) -> bool {
+    let (word, filler) = (runs(word), runs(filler));
+    word.len() == filler.len()
+        && word
+            .iter()
+            .zip(&filler)
+            .all(|(&(a, n), &(b, m))| a == b && (n == m || (n >= 3 && m < n)))
+}
+
+fn starts_upper(word: &str) -> bool {
+    word.chars().next().is_some_and(char::is_uppercase)
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Strip hesitation tokens and accidental repeats from `text`, moving any
+/// sentence punctuation and capitalisation they carried onto their
+/// neighbours.
+pub fn apply(
+    text: &str,
+    extra: &[FillerWord],
+    collapse_repeats: bool,
+    language: &Language,
+) -> String {
+    let applies = |code: &str| language.code().is_none_or(|c| c == code);
+    let languages: Vec<&str> = BUILTIN_LANGUAGES
+        .iter()
+        .copied()
+        .filter(|c| applies(c))
+        .collect();
+
+    let mut fillers: Vec<String> = languages
+        .iter()
+        .flat_map(|c| builtin(c))
+        .map(|w| w.to_string())
+        .collect();
+    fillers.extend(
+        extra
+            .iter()
+            .filter(|f| f.language.as_ref().is_none_or(|l| applies(l.as_str())))
+            .map(|f| core(&f.word)),
+    );
+    let allowed: Vec<&str> = languages
+        .iter()
+        .flat_map(|c| allowed_repeats(c))
+        .copied()
+        .collect();
+
+    let mut kept: Vec<String> = Vec::new();
+    // Set when a dropped word started a sentence, so the next kept word
+    // takes over its capital letter
+    let mut capitalize_next = false;
+
+    let words: Vec<&str> = text.split_whitespace().collect();
+    for (i, word) in words.iter().enumerate() {
+        let word_core = core(word);
+        let at_sentence_start = kept.last().is_none_or(|w| w.ends_with(SENTENCE_END));
+
+        // A capital letter mid-sentence means a name, not a hesitation
+        let is_name = starts_upper(word)
+            && (!at_sentence_start
+                || (NAMES.contains(&word_core.as_str()) && !word.ends_with(PUNCTUATION)));
+        let is_filler = !word_core.is_empty()
+            && !is_name
+            && fillers.iter().any(|f| spells(&word_core, f));
+        let is_stutter = collapse_repeats && {
+            let next_core = words.get(i + 1).map(|w| core(w)).unwrap_or_default();
+            // "th- the" or "the the", but keep "that that", "the, the" and
+            // a dash standing alone
+            let stem = word_core.trim_end_matches('-');
+            let cut_off = word.ends_with('-') && !stem.is_empty() && next_core.starts_with(stem);
+            let doubled = !word_core.is_empty()
+                && word_core == next_core
+                && !word.ends_with(PUNCTUATION)
+                && !allowed.contains(&word_core.as_str());
+            cut_off || doubled
+        };
+
+        if !is_filler && !is_stutter {
+            if capitalize_next {
+                kept.push(capitalize(word));
+                capitalize_next = false;
+            } else {
+                kept.push(word.to_string());
+            }
+            continue;
+        }
+
+        if at_sentence_start && starts_upper(word) {
+            capitalize_next = true;
+        }
+        // "The plan, uh." — the period belongs to the sentence, not the filler
+        if is_filler {
+            if let Some(end) = word.chars().last().filter(|c| SENTENCE_END.contains(c)) {
+                if let Some(prev) = kept.last_mut() {
+                    let trimmed = prev.trim_end_matches(PUNCTUATION).len();
+                    prev.truncate(trimmed);
+                    prev.push(end);
+                }
+            }
+            // "I think, um, we": the commas only set off the filler
+            if word.ends_with(',') {
+                if let Some(prev) = kept.last_mut().filter(|p| p.ends_with(',')) {
+                    prev.pop();
+                }
+            }
+        }
+    }
+
+    kept.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lang(code: &str) -> Language {
+        Language::try_from(code.to_string()).unwrap()
+    }
+
+    fn strip(text: &str, code: &str) -> String {
+        apply(text, &[], true, &lang(code))
+    }
+
+    #[test]
+    fn hesitations_are_removed() {
+        assert_eq!(strip("so um I think uh we should", "en"), "so I think we should");
+        assert_eq!(strip("Ummm, the plan is fine", "en"), "The plan is fine");
+        assert_eq!(strip("The plan, uh.", "en"), "The plan.");
+        assert_eq!(strip("euhhh je pense que oui", "fr"), "je pense que oui");
+    }
+
+    #[test]
+    fn commas_around_a_filler_go_with_it() {
+        assert_eq!(strip("I think, um, we should", "en"), "I think we should");
+        assert_eq!(strip("I think um, we should", "en"), "I think we should");
+        assert_eq!(strip("je pense, euh, qu'on peut", "fr"), "je pense qu'on peut");
+        assert_eq!(strip("first, um second", "en"), "first, second");
+    }
+
+    #[test]
+    fn only_drawn_out_letters_are_squeezed() {
+        assert_eq!(strip("I err on the side of caution", "en"), "I err on the side of caution");
+        assert_eq!(strip("well errr maybe", "en"), "well maybe");
+    }
+
+    #[test]
+    fn names_are_not_fillers() {
+        assert_eq!(strip("Ben said bah okay", "fr"), "Ben said okay");
+        assert_eq!(strip("Ben said bah okay", "auto"), "Ben said okay");
+        assert_eq!(strip("I asked Ben about it", "auto"), "I asked Ben about it");
+        assert_eq!(strip("Ben, je sais pas", "fr"), "Je sais pas");
+        assert_eq!(strip("ben oui", "fr"), "oui");
+    }
+
+    #[test]
+    fn stutters_collapse_but_intensifiers_stay() {
+        assert_eq!(strip("the the cat", "en"), "the cat");
+        assert_eq!(strip("th- the cat", "en"), "the cat");
+        assert_eq!(strip("wait -- the cat", "en"), "wait -- the cat");
+        assert_eq!(strip("wait - -the cat", "en"), "wait - -the cat");
+        assert_eq!(strip("I know that that works", "en"), "I know that that works");
+        assert_eq!(strip("it was so so good", "en"), "it was so so good");
+        assert_eq!(strip("very very big", "en"), "very very big");
+        assert_eq!(apply("the the cat", &[], false, &lang("en")), "the the cat");
+    }
+
+    #[test]
+    fn extra_words_follow_their_language() {
+        let extra = vec![FillerWord {
+            word: "like".to_string(),
+            language: Some(lang("en")),
+        }];
+        assert_eq!(apply("it was like huge", &extra, false, &lang("en")), "it was huge");
+        assert_eq!(apply("like huge", &extra, false, &lang("fr")), "like huge");
+    }
+}
@@ -0,0 +1,915 @@
+//! Rule-based inverse text normalization: spoken numbers, decimals,
+//! percentages, currencies, units and dates become their written forms,
+//! e.g. "twenty three percent" → "23%", "deux cent cinquante euros" → "250 €".
+//!
+//! Single-word numbers below ten ("two people", "un chat") stay as words
+//! unless a unit, a decimal or a date follows, as style guides recommend.
+
+use crate::config::Language;
+
+const NBSP: char = '\u{a0}';
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NumWord {
+    /// 0 to 9
+    Unit(u64),
+    /// 10 to 19
+    Teen(u64),
+    /// 20, 30 … 90 (and French 60 for "soixante")
+    Tens(u64),
+    Hundred,
+    /// 1 000, 1 000 000, 1 000 000 000
+    Scale(u64),
+}
+
+enum UnitStyle {
+    /// Written before the number: "$20"
+    Prefix,
+    /// Glued after the number: "20%", "20°"
+    Attached,
+    /// After the number with a non-breaking space: "20 km"
+    Spaced,
+}
+
+struct Lang {
+    code: &'static str,
+    number: fn(&str) -> Option<NumWord>,
+    /// Joins parts of a number: "one hundred and five", "vingt et un"
+    and: &'static str,
+    decimal_word: &'static str,
+    decimal_sep: char,
+    thousands_sep: char,
+    /// Spoken phrase, symbol, placement. Longest phrases first.
+    units: &'static [(&'static [&'static str], &'static str, UnitStyle)],
+    /// Lower-case spoken name and written name
+    months: [(&'static str, &'static str); 12],
+}
+
+fn en_number(word: &str) -> Option<NumWord> {
+    use NumWord::*;
+    Some(match word {
+        "zero" | "oh" => Unit(0),
+        "one" => Unit(1),
+        "two" => Unit(2),
+        "three" => Unit(3),
+        "four" => Unit(4),
+        "five" => Unit(5),
+        "six" => Unit(6),
+        "seven" => Unit(7),
+        "eight" => Unit(8),
+        "nine" => Unit(9),
+        "ten" => Teen(10),
+        "eleven" => Teen(11),
+        "twelve" => Teen(12),
+        "thirteen" => Teen(13),
+        "fourteen" => Teen(14),
+        "fifteen" => Teen(15),
+        "sixteen" => Teen(16),
+        "seventeen" => Teen(17),
+        "eighteen" => Teen(18),
+        "nineteen" => Teen(19),
+        "twenty" => Tens(20),
+        "thirty" => Tens(30),
+        "forty" => Tens(40),
+        "fifty" => Tens(50),
+        "sixty" => Tens(60),
+        "seventy" => Tens(70),
+        "eighty" => Tens(80),
+        "ninety" => Tens(90),
+        "hundred" => Hundred,
+        "thousand" => Scale(1_000),
+        "million" => Scale(1_000_000),
+        "billion" => Scale(1_000_000_000),
+        _ => return None,
+    })
+}
+
+fn fr_number(word: &str) -> Option<NumWord> {
+    use NumWord::*;
+    Some(match word {
+        "zéro" => Unit(0),
+        "un" | "une" => Unit(1),
+        "deux" => Unit(2),
+        "trois" => Unit(3),
+        "quatre" => Unit(4),
+        "cinq" => Unit(5),
+        "six" => Unit(6),
+        "sept" => Unit(7),
+        "huit" => Unit(8),
+        "neuf" => Unit(9),
+        "dix" => Teen(10),
+        "onze" => Teen(11),
+        "douze" => Teen(12),
+        "treize" => Teen(13),
+        "quatorze" => Teen(14),
+        "quinze" => Teen(15),
+        "seize" => Teen(16),
+        "vingt" | "vingts" => Tens(20),
+        "trente" => Tens(30),
+        "quarante" => Tens(40),
+        "cinquante" => Tens(50),
+        "soixante" => Tens(60),
+        "cent" | "cents" => Hundred,
+        "mille" => Scale(1_000),
+        "million" | "millions" => Scale(1_000_000),
+        "milliard" | "milliards" => Scale(1_000_000_000),
+        _ => return None,
+    })
+}
+
+const EN: Lang = Lang {
+    code: "en",
+    number: en_number,
+    and: "and",
+    decimal_word: "point",
+    decimal_sep: '.',
+    thousands_sep: ',',
+    units: &[
+        (&["kilometers", "per", "hour"], "km/h", UnitStyle::Spaced),
+        (&["kilometres", "per", "hour"], "km/h", UnitStyle::Spaced),
+        (&["miles", "per", "hour"], "mph", UnitStyle::Spaced),
+        (&["per", "cent"], "%", UnitStyle::Attached),
+        (&["percent"], "%", UnitStyle::Attached),
+        (&["degrees"], "°", UnitStyle::Attached),
+        (&["degree"], "°", UnitStyle::Attached),
+        (&["dollars"], "$", UnitStyle::Prefix),
+        (&["dollar"], "$", UnitStyle::Prefix),
+        (&["euros"], "€", UnitStyle::Prefix),
+        (&["euro"], "€", UnitStyle::Prefix),
+        (&["pounds"], "£", UnitStyle::Prefix),
+        (&["kilometers"], "km", UnitStyle::Spaced),
+        (&["kilometres"], "km", UnitStyle::Spaced),
+        (&["kilometer"], "km", UnitStyle::Spaced),
+        (&["kilometre"], "km", UnitStyle::Spaced),
+        (&["meters"], "m", UnitStyle::Spaced),
+        (&["metres"], "m", UnitStyle::Spaced),
+        (&["centimeters"], "cm", UnitStyle::Spaced),
+        (&["centimetres"], "cm", UnitStyle::Spaced),
+        (&["millimeters"], "mm", UnitStyle::Spaced),
+        (&["millimetres"], "mm", UnitStyle::Spaced),
+        (&["kilograms"], "kg", UnitStyle::Spaced),
+        (&["kilos"], "kg", UnitStyle::Spaced),
+        (&["grams"], "g", UnitStyle::Spaced),
+        (&["miles"], "mi", UnitStyle::Spaced),
+        (&["megabytes"], "MB", UnitStyle::Spaced),
+        (&["gigabytes"], "GB", UnitStyle::Spaced),
+    ],
+    months: [
+        ("january", "January"),
+        ("february", "February"),
+        ("march", "March"),
+        ("april", "April"),
+        ("may", "May"),
+        ("june", "June"),
+        ("july", "July"),
+        ("august", "August"),
+        ("september", "September"),
+        ("october", "October"),
+        ("november", "November"),
+        ("december", "December"),
+    ],
+};
+
+const FR: Lang = Lang {
+    code: "fr",
+    number: fr_number,
+    and: "et",
+    decimal_word: "virgule",
+    decimal_sep: ',',
+    thousands_sep: NBSP,
+    units: &[
+        (&["kilomètres", "par", "heure"], "km/h", UnitStyle::Spaced),
+        (&["kilomètres", "heure"], "km/h", UnitStyle::Spaced),
+        (&["pour", "cent"], "%", UnitStyle::Spaced),
+        (&["pourcent"], "%", UnitStyle::Spaced),
+        (&["pourcents"], "%", UnitStyle::Spaced),
+        (&["degrés"], "°", UnitStyle::Attached),
+        (&["degré"], "°", UnitStyle::Attached),
+        (&["euros"], "€", UnitStyle::Spaced),
+        (&["euro"], "€", UnitStyle::Spaced),
+        (&["dollars"], "$", UnitStyle::Spaced),
+        (&["dollar"], "$", UnitStyle::Spaced),
+        (&["kilomètres"], "km", UnitStyle::Spaced),
+        (&["kilomètre"], "km", UnitStyle::Spaced),
+        (&["mètres"], "m", UnitStyle::Spaced),
+        (&["mètre"], "m", UnitStyle::Spaced),
+        (&["centimètres"], "cm", UnitStyle::Spaced),
+        (&["millimètres"], "mm", UnitStyle::Spaced),
+        (&["kilogrammes"], "kg", UnitStyle::Spaced),
+        (&["kilos"], "kg", UnitStyle::Spaced),
+        (&["grammes"], "g", UnitStyle::Spaced),
+        (&["mégaoctets"], "Mo", UnitStyle::Spaced),
+        (&["gigaoctets"], "Go", UnitStyle::Spaced),
+    ],
+    months: [
+        ("janvier", "janvier"),
+        ("février", "février"),
+        ("mars", "mars"),
+        ("avril", "avril"),
+        ("mai", "mai"),
+        ("juin", "juin"),
+        ("juillet", "juillet"),
+        ("août", "août"),
+        ("septembre", "septembre"),
+        ("octobre", "octobre"),
+        ("novembre", "novembre"),
+        ("décembre", "décembre"),
+    ],
+};
+
+/// A word of the input with the punctuation around it set aside.
+#[derive(Debug, Clone)]
+struct Token {
+    /// Second or later part of a hyphenated word
+    joined: bool,
+    lead: String,
+    word: String,
+    lower: String,
+    trail: String,
+}
+
+const PUNCTUATION: &[char] = &[
+    ',', '.', ';', ':', '!', '?', '"', '(', ')', '«', '»', '…', NBSP,
+];
+
+/// Split on whitespace, and split hyphenated number words ("twenty-three",
+/// "quatre-vingt-dix") into their parts.
+fn tokenize(text: &str, lang: &Lang) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    for raw in text.split_whitespace() {
+        let word = raw.trim_matches(PUNCTUATION);
+        if word.is_empty() {
+            tokens.push(Token {
+                joined: false,
+                lead: raw.to_string(),
+                word: String::new(),
+                lower: String::new(),
+                trail: String::new(),
+            });
+            continue;
+        }
+        let start = raw.len() - raw.trim_start_matches(PUNCTUATION).len();
+        let lead = raw[..start].to_string();
+        let trail = raw[start + word.len()..].to_string();
+        let lower = word.to_lowercase();
+
+        let parts: Vec<&str> = lower.split('-').collect();
+        let all_numeric = parts.len() > 1
+            && parts.iter().all(|p| {
+                (lang.number)(p).is_some() || *p == lang.and || ordinal(lang, p).is_some()
+            });
+        if all_numeric {
+            let last = parts.len() - 1;
+            for (i, part) in parts.iter().enumerate() {
+                tokens.push(Token {
+                    joined: i > 0,
+                    lead: if i == 0 { lead.clone() } else { String::new() },
+                    word: part.to_string(),
+                    lower: part.to_string(),
+                    trail: if i == last {
+                        trail.clone()
+                    } else {
+                        String::new()
+                    },
+                });
+            }
+        } else {
+            tokens.push(Token {
+                joined: false,
+                lead,
+                word: word.to_string(),
+                lower,
+                trail,
+            });
+        }
+    }
+    tokens
+}
+
+/// A number spelled over `len` tokens starting at `start`.
+struct Parsed {
+    value: u64,
+    len: usize,
+}
+
+/// Read the longest well-formed number starting at `start`. A number never
+/// spans punctuation, so "twenty, three" stays two numbers.
+fn parse_number(tokens: &[Token], start: usize, lang: &Lang) -> Option<Parsed> {
+    let fr = lang.code == "fr";
+    let mut total = 0u64;
+    let mut current = 0u64;
+    let mut last_scale = u64::MAX;
+    let mut len = 0;
+    // Whether the last token read was "hundred" or a scale, after which
+    // English allows "and"
+    let mut after_multiplier = false;
+    let mut i = start;
+
+    while i < tokens.len() {
+        if i > start && !tokens[i - 1].trail.is_empty() {
+            break;
+        }
+        if i > start && !tokens[i].lead.is_empty() {
+            break;
+        }
+        let lower = tokens[i].lower.as_str();
+
+        // "oh" is only a digit when one follows, as in "oh five"; otherwise
+        // it is the interjection: "oh ten people came"
+        if i == start && lower == "oh" {
+            match tokens.get(i + 1).and_then(|t| (lang.number)(&t.lower)) {
+                Some(NumWord::Unit(1..)) if tokens[i].trail.is_empty() => {}
+                _ => return None,
+            }
+        }
+
+        // "a hundred", "a thousand"
+        if i == start && lang.code == "en" && lower == "a" {
+            match tokens.get(i + 1).and_then(|t| (lang.number)(&t.lower)) {
+                Some(NumWord::Hundred | NumWord::Scale(_)) => {
+                    current = 1;
+                    i += 1;
+                    continue;
+                }
+                _ => return None,
+            }
+        }
+
+        // "one hundred and five", "vingt et un", "soixante et onze"
+        if lower == lang.and && i > start && tokens[i - 1].trail.is_empty() {
+            let next = tokens.get(i + 1).and_then(|t| (lang.number)(&t.lower));
+            let joins = if fr {
+                matches!(
+                    (current % 100, next),
+                    (20 | 30 | 40 | 50 | 60, Some(NumWord::Unit(1)))
+                        | (60, Some(NumWord::Teen(11)))
+                )
+            } else {
+                after_multiplier
+                    && matches!(
+                        next,
+                        Some(NumWord::Unit(1..) | NumWord::Teen(_) | NumWord::Tens(_))
+                    )
+            };
+            if !joins {
+                break;
+            }
+            i += 1;
+            continue;
+        }
+
+        let Some(word) = (lang.number)(lower) else {
+            break;
+        };
+        let tail = current % 100;
+        let ok = match word {
+            NumWord::Unit(0) => i == start && len == 0,
+            NumWord::Unit(_) => {
+                current % 10 == 0
+                    && !(11..=19).contains(&tail)
+                    && (fr || tail != 10)
+                    // "vingt-huit" or "cent huit", but not "deux trois" or "oh five"
+                    && (len == 0 || tail != 0 || after_multiplier)
+            }
+            NumWord::Teen(_) => tail == 0 || (fr && (tail == 60 || tail == 80)),
+            NumWord::Tens(20) if fr && tail == 4 => true,
+            NumWord::Tens(_) => tail == 0 && (len == 0 || after_multiplier),
+            NumWord::Hundred => current < 100 && (current > 0 || fr),
+            NumWord::Scale(scale) => scale < last_scale && (current > 0 || fr),
+        };
+        if !ok {
+            break;
+        }
+
+        match word {
+            NumWord::Unit(n) | NumWord::Teen(n) => current += n,
+            // "quatre-vingt": 4 × 20
+            NumWord::Tens(20) if fr && tail == 4 => current += 76,
+            NumWord::Tens(n) => current += n,
+            NumWord::Hundred => current = current.max(1) * 100,
+            NumWord::Scale(scale) => {
+                total += current.max(1) * scale;
+                current = 0;
+                last_scale = scale;
+            }
+        }
+        after_multiplier = matches!(word, NumWord::Hundred | NumWord::Scale(_));
+        len = i - start + 1;
+        i += 1;
+        // Zero never combines: "zero ten" is two numbers
+        if word == NumWord::Unit(0) {
+            break;
+        }
+    }
+
+    (len > 0).then_some(Parsed {
+        value: total + current,
+        len,
+    })
+}
+
+fn ordinal(lang: &Lang, word: &str) -> Option<u64> {
+    let n = match (lang.code, word) {
+        ("en", "first") => 1,
+        ("en", "second") => 2,
+        ("en", "third") => 3,
+        ("en", "fourth") => 4,
+        ("en", "fifth") => 5,
+        ("en", "sixth") => 6,
+        ("en", "seventh") => 7,
+        ("en", "eighth") => 8,
+        ("en", "ninth") => 9,
+        ("en", "tenth") => 10,
+        ("en", "eleventh") => 11,
+        ("en", "twelfth") => 12,
+        ("en", "thirteenth") => 13,
+        ("en", "fourteenth") => 14,
+        ("en", "fifteenth") => 15,
+        ("en", "sixteenth") => 16,
+        ("en", "seventeenth") => 17,
+        ("en", "eighteenth") => 18,
+        ("en", "nineteenth") => 19,
+        ("en", "twentieth") => 20,
+        ("en", "thirtieth") => 30,
+        ("fr", "premier") | ("fr", "1er") => 1,
+        _ => return None,
+    };
+    Some(n)
+}
+
+/// A day of the month spoken as an ordinal ("fifth", "twenty-first") in
+/// English, or as "premier" or a cardinal in French.
+fn parse_day(tokens: &[Token], start: usize, lang: &Lang) -> Option<Parsed> {
+    let token = tokens.get(start)?;
+    if let Some(value) = ordinal(lang, &token.lower) {
+        return Some(Parsed { value, len: 1 });
+    }
+    if lang.code == "en" {
+        // "twenty first"
+        let tens = match en_number(&token.lower) {
+            Some(NumWord::Tens(n @ (20 | 30))) => n,
+            _ => return None,
+        };
+        if !token.trail.is_empty() {
+            return None;
+        }
+        let unit = ordinal(lang, &tokens.get(start + 1)?.lower).filter(|n| *n < 10)?;
+        return Some(Parsed {
+            value: tens + unit,
+            len: 2,
+        });
+    }
+    parse_number(tokens, start, lang).filter(|p| (1..=31).contains(&p.value))
+}
+
+/// A year: "two thousand twenty six", "deux mille vingt-six", or the English
+/// pairs "twenty twenty six", "nineteen eighty four", "twenty oh five".
+fn parse_year(tokens: &[Token], start: usize, lang: &Lang) -> Option<Parsed> {
+    let first = parse_number(tokens, start, lang)?;
+    if (1000..=2999).contains(&first.value) {
+        return Some(first);
+    }
+    if lang.code != "en" || !(10..=99).contains(&first.value) {
+        return None;
+    }
+    let next = start + first.len;
+    if !tokens[next - 1].trail.is_empty() {
+        return None;
+    }
+    let second = parse_number(tokens, next, lang)?;
+    let value = match (tokens[next].lower.as_str(), second.value) {
+        ("oh", _) if second.len == 1 => {
+            // "twenty oh five"
+            let unit =
+                parse_number(tokens, next + 1, lang).filter(|p| p.value < 10 && p.len == 1)?;
+            return Some(Parsed {
+                value: first.value * 100 + unit.value,
+                len: first.len + 2,
+            });
+        }
+        (_, 10..=99) => first.value * 100 + second.value,
+        _ => return None,
+    };
+    Some(Parsed {
+        value,
+        len: first.len + second.len,
+    })
+}
+
+/// Decades as in "the nineteen nineties".
+fn decade(word: &str) -> Option<u64> {
+    let decades = [
+        "hundreds",
+        "tens",
+        "twenties",
+        "thirties",
+        "forties",
+        "fifties",
+        "sixties",
+        "seventies",
+        "eighties",
+        "nineties",
+    ];
+    decades
+        .iter()
+        .position(|d| *d == word)
+        .map(|k| k as u64 * 10)
+}
+
+/// English years outside a date, only when they start with "nineteen" or
+/// "twenty" so counts like "ten twenty" are left alone: "in twenty twenty
+/// six" → "in 2026", "the nineteen nineties" → "the 1990s".
+fn rewrite_year_pair(tokens: &[Token], i: usize, lang: &Lang) -> Option<Rewrite> {
+    let century = parse_number(tokens, i, lang)
+        .filter(|p| p.len == 1 && matches!(p.value, 19 | 20) && tokens[i].trail.is_empty())?;
+    if let Some(decade) = tokens.get(i + 1).and_then(|t| decade(&t.lower)) {
+        return Some(Rewrite {
+            text: format!("{}s", century.value * 100 + decade),
+            len: 2,
+        });
+    }
+    let year = parse_year(tokens, i, lang).filter(|y| y.len > 1)?;
+    Some(Rewrite {
+        text: year.value.to_string(),
+        len: year.len,
+    })
+}
+
+fn month(tokens: &[Token], i: usize, lang: &Lang) -> Option<&'static str> {
+    let lower = &tokens.get(i)?.lower;
+    lang.months
+        .iter()
+        .find(|(spoken, _)| spoken == lower)
+        .map(|(_, written)| *written)
+}
+
+/// Month names that are also everyday words ("you may first want to",
+/// "they march second"): only a month when written with a capital, or
+/// with "the" before the day or a year after it.
+const AMBIGUOUS_MONTHS: &[&str] = &["may", "march"];
+
+/// Output of a rule: replaces `len` tokens starting at the current one.
+struct Rewrite {
+    text: String,
+    len: usize,
+}
+
+/// English: "[the] fifth of March [year]" → "5 March [year]",
+/// "March [the] fifth [year]" → "March 5[, year]".
+/// French: "cinq mars [année]" → "5 mars [année]", "premier" → "1er".
+fn rewrite_date(tokens: &[Token], i: usize, lang: &Lang) -> Option<Rewrite> {
+    let mut j = i;
+    let year_at = |j: usize| {
+        if tokens[j - 1].trail.is_empty() || tokens[j - 1].trail == "," {
+            parse_year(tokens, j, lang)
+        } else {
+            None
+        }
+    };
+
+    if lang.code == "en" {
+        if tokens[j].lower == "the" {
+            j += 1;
+        }
+        // Day first
+        if let Some(day) = parse_day(tokens, j, lang) {
+            let of = j + day.len;
+            if tokens.get(of).is_some_and(|t| t.lower == "of") {
+                if let Some(name) = month(tokens, of + 1, lang) {
+                    let mut end = of + 2;
+                    let mut text = format!("{} {}", day.value, name);
+                    if let Some(year) = tokens.get(end).and_then(|_| year_at(end)) {
+                        text.push_str(&format!(" {}", year.value));
+                        end += year.len;
+                    }
+                    return Some(Rewrite { text, len: end - i });
+                }
+            }
+            return None;
+        }
+        // Month first
+        let name = month(tokens, i, lang)?;
+        let mut j = i + 1;
+        if tokens.get(j).is_some_and(|t| t.lower == "the") {
+            j += 1;
+        }
+        let day = parse_day(tokens, j, lang)?;
+        let mut end = j + day.len;
+        let mut text = format!("{} {}", name, day.value);
+        let year = tokens.get(end).and_then(|_| year_at(end));
+        if AMBIGUOUS_MONTHS.contains(&tokens[i].lower.as_str())
+            && !tokens[i].word.starts_with(char::is_uppercase)
+            && j == i + 1
+            && year.is_none()
+        {
+            return None;
+        }
+        if let Some(year) = year {
+            text.push_str(&format!(", {}", year.value));
+            end += year.len;
+        }
+        return Some(Rewrite { text, len: end - i });
+    }
+
+    let day = parse_day(tokens, j, lang)?;
+    j += day.len;
+    if !tokens[j - 1].trail.is_empty() {
+        return None;
+    }
+    let name = month(tokens, j, lang)?;
+    let mut end = j + 1;
+    let day_text = if day.value == 1 {
+        "1er".to_string()
+    } else {
+        day.value.to_string()
+    };
+    let mut text = format!("{} {}", day_text, name);
+    if let Some(year) = tokens.get(end).and_then(|_| year_at(end)) {
+        text.push_str(&format!(" {}", year.value));
+        end += year.len;
+    }
+    Some(Rewrite { text, len: end - i })
+}
+
+fn group_thousands(value: u64, sep: char) -> String {
+    let digits = value.to_string();
+    // Four-digit numbers read better ungrouped, and years must stay so
+    if digits.len() <= 4 {
+        return digits;
+    }
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            out.push(sep);
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// "three point two", "trois virgule cinq", "version one point two point
+/// three": digits after the decimal word are read one by one, or as one
+/// number ("point twenty five").
+fn parse_decimals(tokens: &[Token], start: usize, lang: &Lang) -> Option<(Vec<String>, usize)> {
+    let mut parts = Vec::new();
+    let mut i = start;
+    while tokens.get(i).is_some_and(|t| t.lower == lang.decimal_word)
+        && tokens[i - 1].trail.is_empty()
+        && tokens[i].trail.is_empty()
+    {
+        let mut digits = String::new();
+        let mut j = i + 1;
+        while let Some(NumWord::Unit(n)) = tokens.get(j).and_then(|t| (lang.number)(&t.lower)) {
+            digits.push_str(&n.to_string());
+            j += 1;
+            if !tokens[j - 1].trail.is_empty() {
+                break;
+            }
+        }
+        if digits.is_empty() {
+            let number = parse_number(tokens, i + 1, lang)?;
+            digits = number.value.to_string();
+            j = i + 1 + number.len;
+        }
+        parts.push(digits);
+        i = j;
+    }
+    (!parts.is_empty()).then_some((parts, i - start))
+}
+
+fn rewrite_number(tokens: &[Token], i: usize, lang: &Lang) -> Option<Rewrite> {
+    if lang.code == "en" {
+        if let Some(year) = rewrite_year_pair(tokens, i, lang) {
+            return Some(year);
+        }
+    }
+
+    let word = &tokens[i].word;
+    // Figures the engine already wrote only pick up a unit: "3 kilometers"
+    let (mut text, len, mut forced) =
+        if !word.is_empty() && word.bytes().all(|b| b.is_ascii_digit()) {
+            (word.clone(), 1, false)
+        } else {
+            let number = parse_number(tokens, i, lang)?;
+            // "a million" alone is an idiom ("thanks a million"), not a count
+            let bare_a = tokens[i].lower == "a" && number.len == 2;
+            (
+                group_thousands(number.value, lang.thousands_sep),
+                number.len,
+                !bare_a && (number.len > 1 || number.value >= 10),
+            )
+        };
+    let mut end = i + len;
+
+    if let Some((parts, len)) = parse_decimals(tokens, end, lang) {
+        let after = end + len;
+        // "at one point two people left": one digit after the decimal word,
+        // then more words, is not a figure unless a unit follows
+        let numeric = parts.len() > 1
+            || parts[0].len() > 1
+            || after == tokens.len()
+            || !tokens[after - 1].trail.is_empty()
+            || unit_at(tokens, after, lang).is_some();
+        if numeric {
+            for part in parts {
+                text.push(lang.decimal_sep);
+                text.push_str(&part);
+            }
+            end = after;
+            forced = true;
+        }
+    }
+
+    if let Some((phrase, symbol, style)) = unit_at(tokens, end, lang) {
+        text = match style {
+            UnitStyle::Prefix => format!("{}{}", symbol, text),
+            UnitStyle::Attached => format!("{}{}", text, symbol),
+            UnitStyle::Spaced => format!("{}{}{}", text, NBSP, symbol),
+        };
+        end += phrase.len();
+        forced = true;
+    }
+
+    forced.then_some(Rewrite { text, len: end - i })
+}
+
+/// The unit spoken right after a number ending before `end`.
+fn unit_at(
+    tokens: &[Token],
+    end: usize,
+    lang: &Lang,
+) -> Option<&'static (&'static [&'static str], &'static str, UnitStyle)> {
+    if !tokens[end - 1].trail.is_empty() {
+        return None;
+    }
+    lang.units.iter().find(|(phrase, _, _)| {
+        phrase.iter().enumerate().all(|(k, w)| {
+            tokens
+                .get(end + k)
+                .is_some_and(|t| t.lower == *w && (k + 1 == phrase.len() || t.trail.is_empty()))
+        })
+    })
+}
+
+fn normalize_lang(text: &str, lang: &Lang) -> String {
+    let tokens = tokenize(text, lang);
+    let mut out: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let rewrite = rewrite_date(&tokens, i, lang)
+            .or_else(|| rewrite_number(&tokens, i, lang))
+            // Never rewrite part of a hyphenated word: "twenty-first" alone
+            // is not a date and stays as written
+            .filter(|r| !tokens[i].joined && !tokens.get(i + r.len).is_some_and(|t| t.joined));
+        match rewrite {
+            Some(r) => {
+                let last = &tokens[i + r.len - 1];
+                out.push(format!("{}{}{}", tokens[i].lead, r.text, last.trail));
+                i += r.len;
+            }
+            None => {
+                let t = &tokens[i];
+                let word = format!("{}{}{}", t.lead, t.word, t.trail);
+                match out.last_mut() {
+                    // Rejoin hyphenated words that did not form a number
+                    Some(prev) if t.joined => {
+                        prev.push('-');
+                        prev.push_str(&word);
+                    }
+                    _ => out.push(word),
+                }
+                i += 1;
+            }
+        }
+    }
+    out.join(" ")
+}
+
+/// Common words that tell English and French dictation apart ("a" is both).
+const EN_MARKERS: &[&str] = &[
+    "the", "and", "is", "of", "to", "it", "you", "that", "in", "i", "was", "for",
+];
+const FR_MARKERS: &[&str] = &[
+    "le", "la", "les", "et", "est", "de", "des", "du", "un", "une", "je", "que", "à", "pour",
+];
+
+/// Rule set for auto-detected dictation, chosen from the text since the
+/// engines do not report the language they heard. English unless the text
+/// reads more French.
+fn detect(text: &str) -> &'static Lang {
+    let (mut en, mut fr) = (0, 0);
+    for word in text.split_whitespace() {
+        let word = word.trim_matches(PUNCTUATION).to_lowercase();
+        en += EN_MARKERS.contains(&word.as_str()) as usize;
+        fr += FR_MARKERS.contains(&word.as_str()) as usize;
+        fr += word.contains(['é', 'è', 'ê', 'à', 'ç', 'û']) as usize;
+    }
+    if fr > en {
+        &FR
+    } else {
+        &EN
+    }
+}
+
+/// Rewrite spoken numbers, dates and units in `text` for `language`; with
+/// auto-detection the English or French rules run, whichever the text is
+/// written in.
+pub fn apply(text: &str, language: &Language) -> String {
+    match language.code() {
+        Some("en") => normalize_lang(text, &EN),
+        Some("fr") => normalize_lang(text, &FR),
+        Some(_) => text.to_string(),
+        None => normalize_lang(text, detect(text)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn itn(text: &str, code: &str) -> String {
+        apply(text, &Language::try_from(code.to_string()).unwrap())
+    }
+
+    #[test]
+    fn requested_examples() {
+        assert_eq!(itn("twenty three percent", "en"), "23%");
+        assert_eq!(
+            itn("the fifth of March twenty twenty six", "en"),
+            "5 March 2026"
+        );
+        assert_eq!(
+            itn("deux cent cinquante euros", "fr"),
+            format!("250{}€", NBSP)
+        );
+        assert_eq!(itn("version three point two", "en"), "version 3.2");
+    }
+
+    #[test]
+    fn numbers_units_and_dates() {
+        assert_eq!(itn("two people", "en"), "two people");
+        assert_eq!(itn("one hundred and five", "en"), "105");
+        assert_eq!(itn("twenty dollars", "en"), "$20");
+        assert_eq!(itn("quatre-vingt-dix", "fr"), "90");
+        assert_eq!(itn("March the first", "en"), "March 1");
+        assert_eq!(itn("premier mai", "fr"), "1er mai");
+        assert_eq!(itn("in twenty oh five", "en"), "in 2005");
+        assert_eq!(itn("twenty, three", "en"), "20, three");
+    }
+
+    #[test]
+    fn may_is_a_month_only_in_a_date() {
+        assert_eq!(
+            itn("you may first want to check", "en"),
+            "you may first want to check"
+        );
+        assert_eq!(itn("I may second that motion", "en"), "I may second that motion");
+        assert_eq!(itn("on May first", "en"), "on May 1");
+        assert_eq!(itn("on may the first", "en"), "on May 1");
+        assert_eq!(
+            itn("may fifth twenty twenty six", "en"),
+            "May 5, 2026"
+        );
+    }
+
+    #[test]
+    fn oh_is_not_zero_on_its_own() {
+        assert_eq!(itn("oh ten people came", "en"), "oh 10 people came");
+        assert_eq!(itn("oh well", "en"), "oh well");
+        assert_eq!(itn("zero ten", "en"), "zero 10");
+    }
+
+    #[test]
+    fn a_scale_needs_more_number() {
+        assert_eq!(itn("thanks a million", "en"), "thanks a million");
+        assert_eq!(itn("a thousand times, no", "en"), "a thousand times, no");
+        assert_eq!(itn("a hundred dollars", "en"), "$100");
+        assert_eq!(itn("a hundred and five", "en"), "105");
+        assert_eq!(itn("a thousand twenty", "en"), "1020");
+    }
+
+    #[test]
+    fn decimals_need_a_numeric_context() {
+        assert_eq!(
+            itn("at one point two people left", "en"),
+            "at one point two people left"
+        );
+        assert_eq!(
+            itn("it weighs one point two kilograms", "en"),
+            format!("it weighs 1.2{}kg", NBSP)
+        );
+        assert_eq!(itn("one point two, then", "en"), "1.2, then");
+        assert_eq!(itn("one point two five of them", "en"), "1.25 of them");
+        assert_eq!(itn("release one point two point three today", "en"), "release 1.2.3 today");
+    }
+
+    #[test]
+    fn auto_runs_one_language() {
+        assert_eq!(itn("it costs one cent", "auto"), "it costs one cent");
+        assert_eq!(itn("ten percent of the cent", "auto"), "10% of the cent");
+        assert_eq!(
+            itn("il a payé deux cent cinquante euros", "auto"),
+            format!("il a payé 250{}€", NBSP)
+        );
+    }
+}
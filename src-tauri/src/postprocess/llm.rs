@@ -0,0 +1,203 @@
+use reqwest::header::CONTENT_TYPE;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Duration;
+
+/// Settings of the LLM post-editing step.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LlmEdit {
+    /// Full URL of an OpenAI-compatible chat completions endpoint, e.g.
+    /// `http://localhost:8080/v1/chat/completions` (llama.cpp server) or
+    /// `http://localhost:11434/v1/chat/completions` (Ollama)
+    pub endpoint: String,
+    /// Model name sent with the request; llama.cpp server ignores it
+    #[serde(default)]
+    pub model: String,
+    /// Sent as a bearer token when not empty
+    #[serde(default)]
+    pub api_key: String,
+    /// Instructions sent with the transcript. `{text}` is replaced by the
+    /// transcript, which is appended when the prompt does not mention it.
+    pub prompt: String,
+    /// Give up and keep the transcript unchanged after this long
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+fn default_timeout_ms() -> u64 {
+    10_000
+}
+
+/// Keeps chatty models from wrapping the result in commentary.
+const SYSTEM_PROMPT: &str = "You edit dictated text. Reply with the edited text only, \
+without quotes, notes or explanations.";
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<Choice>,
+}
+
+#[derive(Deserialize)]
+struct Choice {
+    message: Message,
+}
+
+#[derive(Deserialize)]
+struct Message {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+/// Drop the `<think>…</think>` block reasoning models put before their
+/// answer.
+fn strip_reasoning(reply: &str) -> &str {
+    match (reply.trim_start().strip_prefix("<think>"), reply.find("</think>")) {
+        (Some(_), Some(end)) => &reply[end + "</think>".len()..],
+        _ => reply,
+    }
+}
+
+impl LlmEdit {
+    /// The edited text, or `text` unchanged when the endpoint fails, times
+    /// out or answers nothing.
+    pub fn apply(&self, text: &str) -> String {
+        if text.trim().is_empty() {
+            return text.to_string();
+        }
+        match self.request(text) {
+            Ok(edited) if !edited.is_empty() => edited,
+            Ok(_) => {
+                eprintln!("LLM post-edit returned nothing, keeping the transcript");
+                text.to_string()
+            }
+            Err(e) => {
+                eprintln!("LLM post-edit failed, keeping the transcript: {}", e);
+                text.to_string()
+            }
+        }
+    }
+
+    fn request(&self, text: &str) -> Result<String, String> {
+        let prompt = if self.prompt.contains("{text}") {
+            self.prompt.replace("{text}", text)
+        } else {
+            format!("{}\n\n{}", self.prompt, text)
+        };
+        let body = json!({
+            "model": self.model,
+            "messages": [
+                { "role": "system", "content": SYSTEM_PROMPT },
+                { "role": "user", "content": prompt },
+            ],
+            "temperature": 0.2,
+            "stream": false,
+        });
+
+        let client = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_millis(self.timeout_ms))
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+        let mut request = client
+            .post(&self.endpoint)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body.to_string());
+        if !self.api_key.is_empty() {
+            request = request.bearer_auth(&self.api_key);
+        }
+
+        let response = request
+            .send()
+            .map_err(|e| format!("Request to {} failed: {}", self.endpoint, e))?;
+        let status = response.status();
+        let body = response
+            .text()
+            .map_err(|e| format!("Failed to read response: {}", e))?;
+        if !status.is_success() {
+            return Err(format!("{} answered {}: {}", self.endpoint, status, body.trim()));
+        }
+
+        let reply: ChatResponse =
+            serde_json::from_str(&body).map_err(|e| format!("Invalid response: {}", e))?;
+        let content = reply
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|c| c.message.content)
+            .unwrap_or_default();
+        Ok(strip_reasoning(&content).trim().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::Stub;
+
+    fn edit(endpoint: String, prompt: &str, timeout_ms: u64) -> LlmEdit {
+        LlmEdit {
+            endpoint,
+            model: "test".to_string(),
+            api_key: "secret".to_string(),
+            prompt: prompt.to_string(),
+            timeout_ms,
+        }
+    }
+
+    fn reply(content: &str) -> String {
+        json!({ "choices": [{ "message": { "content": content } }] }).to_string()
+    }
+
+    const PATH: &str = "/v1/chat/completions";
+
+    #[test]
+    fn edited_text_replaces_the_transcript() {
+        let server = Stub::answer("200 OK", reply("Hello, world."));
+        let llm = edit(server.url(PATH), "Fix the punctuation of: {text} (keep the words)", 5_000);
+        assert_eq!(llm.apply("hello world"), "Hello, world.");
+
+        let request = server.request();
+        assert!(request.head.starts_with("POST /v1/chat/completions"));
+        assert_eq!(request.header("authorization"), Some("Bearer secret"));
+        let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+        assert_eq!(body["model"], "test");
+        assert_eq!(
+            body["messages"][1]["content"],
+            "Fix the punctuation of: hello world (keep the words)"
+        );
+    }
+
+    #[test]
+    fn prompt_without_placeholder_gets_the_text_appended() {
+        let server = Stub::answer("200 OK", reply("ok"));
+        edit(server.url(PATH), "Fix this.", 5_000).apply("hello");
+        let body: serde_json::Value = serde_json::from_slice(&server.request().body).unwrap();
+        assert_eq!(body["messages"][1]["content"], "Fix this.\n\nhello");
+    }
+
+    #[test]
+    fn error_status_keeps_the_transcript() {
+        let server = Stub::answer("500 Internal Server Error", r#"{"error":"model not loaded"}"#);
+        let llm = edit(server.url(PATH), "{text}", 5_000);
+        assert_eq!(llm.apply("hello world"), "hello world");
+        server.request();
+    }
+
+    #[test]
+    fn timeout_keeps_the_transcript() {
+        let server = Stub::silent(Duration::from_secs(1));
+        let started = std::time::Instant::now();
+        assert_eq!(edit(server.url(PATH), "{text}", 200).apply("hello world"), "hello world");
+        assert!(started.elapsed() < Duration::from_secs(1));
+        server.request();
+    }
+
+    #[test]
+    fn reasoning_is_stripped() {
+        assert_eq!(strip_reasoning("<think>hmm</think>\nDone."), "\nDone.");
+        assert_eq!(strip_reasoning("No <think> here"), "No <think> here");
+
+        let server = Stub::answer("200 OK", reply("<think>The user wants…</think>\n\nHello."));
+        assert_eq!(edit(server.url(PATH), "{text}", 5_000).apply("hello"), "Hello.");
+        server.request();
+    }
+}
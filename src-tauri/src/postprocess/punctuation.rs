@@ -0,0 +1,333 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::Language;
+
+/// How a spoken symbol attaches to the words around it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Join {
+    /// Glued to the previous word, e.g. `,` or `?`
+    Previous,
+    /// Glued to the next word, e.g. `(`
+    Next,
+    /// No space on either side, e.g. a line break
+    Both,
+    /// Stands alone like a word, e.g. `&`
+    #[default]
+    Neither,
+}
+
+/// A dictation phrase and the text it turns into.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PunctuationRule {
+    /// Words to say, matched case-insensitively, e.g. `"question mark"`
+    pub phrase: String,
+    pub text: String,
+    #[serde(default)]
+    pub join: Join,
+    /// Only active when dictating in this language; `None` for all
+    #[serde(default)]
+    pub language: Option<Language>,
+}
+
+const NBSP: &str = "\u{a0}";
+
+/// Built-in phrases per language code.
+fn builtin(code: &str) -> Vec<(&'static str, String, Join)> {
+    let s = |text: &str| text.to_string();
+    match code {
+        "en" => vec![
+            ("new paragraph", s("\n\n"), Join::Both),
+            ("new line", s("\n"), Join::Both),
+            ("comma", s(","), Join::Previous),
+            ("full stop", s("."), Join::Previous),
+            ("period", s("."), Join::Previous),
+            ("question mark", s("?"), Join::Previous),
+            ("exclamation mark", s("!"), Join::Previous),
+            ("exclamation point", s("!"), Join::Previous),
+            ("colon", s(":"), Join::Previous),
+            ("semicolon", s(";"), Join::Previous),
+            ("open quote", s("\""), Join::Next),
+            ("close quote", s("\""), Join::Previous),
+            ("end quote", s("\""), Join::Previous),
+            ("open parenthesis", s("("), Join::Next),
+            ("close parenthesis", s(")"), Join::Previous),
+        ],
+        // French typography puts a non-breaking space before ? ! : ; and
+        // inside guillemets
+        "fr" => vec![
+            ("nouveau paragraphe", s("\n\n"), Join::Both),
+            ("retour à la ligne", s("\n"), Join::Both),
+            ("à la ligne", s("\n"), Join::Both),
+            ("virgule", s(","), Join::Previous),
+            ("point final", s("."), Join::Previous),
+            (
+                "point d'interrogation",
+                format!("{}?", NBSP),
+                Join::Previous,
+            ),
+            ("point d'exclamation", format!("{}!", NBSP), Join::Previous),
+            ("point-virgule", format!("{};", NBSP), Join::Previous),
+            ("point virgule", format!("{};", NBSP), Join::Previous),
+            ("deux-points", format!("{}:", NBSP), Join::Previous),
+            ("deux points", format!("{}:", NBSP), Join::Previous),
+            ("ouvrez les guillemets", format!("«{}", NBSP), Join::Next),
+            ("ouvrir les guillemets", format!("«{}", NBSP), Join::Next),
+            (
+                "fermez les guillemets",
+                format!("{}»", NBSP),
+                Join::Previous,
+            ),
+            (
+                "fermer les guillemets",
+                format!("{}»", NBSP),
+                Join::Previous,
+            ),
+            ("ouvrez la parenthèse", s("("), Join::Next),
+            ("ouvrir la parenthèse", s("("), Join::Next),
+            ("fermez la parenthèse", s(")"), Join::Previous),
+            ("fermer la parenthèse", s(")"), Join::Previous),
+        ],
+        _ => Vec::new(),
+    }
+}
+
+const BUILTIN_LANGUAGES: &[&str] = &["en", "fr"];
+
+/// Built-in phrases that are also everyday words ("the trial period ended"):
+/// only converted at the end of a sentence, i.e. last, followed by
+/// punctuation, a capital or another spoken symbol.
+const SENTENCE_END_ONLY: &[&str] = &["period"];
+
+/// Word that keeps the phrase after it as written: "literal colon".
+fn escape_word(code: &str) -> Option<&'static str> {
+    match code {
+        "en" => Some("literal"),
+        "fr" => Some("littéralement"),
+        _ => None,
+    }
+}
+
+/// Punctuation the engine may have put around a spoken command
+const ENGINE_PUNCTUATION: &[char] = &[',', '.', ';', ':', '!', '?'];
+
+/// Comparable form of a word: lower case, typographic apostrophes
+/// straightened, surrounding punctuation removed.
+fn normalize(word: &str) -> String {
+    word.trim_matches(ENGINE_PUNCTUATION)
+        .replace('’', "'")
+        .to_lowercase()
+}
+
+struct Rule {
+    words: Vec<String>,
+    text: String,
+    join: Join,
+    sentence_end_only: bool,
+}
+
+/// User rules first so they can override built-in phrases, then longest
+/// phrases first so "new paragraph" wins over a user's "new".
+fn rules_for(extra: &[PunctuationRule], language: &Language) -> Vec<Rule> {
+    let applies = |code: &str| language.code().is_none_or(|c| c == code);
+
+    let mut rules: Vec<Rule> = extra
+        .iter()
+        .filter(|r| r.language.as_ref().is_none_or(|l| applies(l.as_str())))
+        .map(|r| Rule {
+            words: r.phrase.split_whitespace().map(normalize).collect(),
+            text: r.text.clone(),
+            join: r.join,
+            sentence_end_only: false,
+        })
+        .filter(|r| !r.words.is_empty() && r.words.iter().all(|w| !w.is_empty()))
+        .collect();
+
+    for code in BUILTIN_LANGUAGES.iter().filter(|c| applies(c)) {
+        rules.extend(builtin(code).into_iter().map(|(phrase, text, join)| Rule {
+            words: phrase.split_whitespace().map(normalize).collect(),
+            text,
+            join,
+            sentence_end_only: SENTENCE_END_ONLY.contains(&phrase),
+        }));
+    }
+
+    rules.sort_by_key(|r| std::cmp::Reverse(r.words.len()));
+    rules
+}
+
+/// Replace spoken punctuation phrases in `text` with the symbols they name.
+pub fn apply(text: &str, extra: &[PunctuationRule], language: &Language) -> String {
+    let rules = rules_for(extra, language);
+    let escapes: Vec<&str> = BUILTIN_LANGUAGES
+        .iter()
+        .filter(|c| language.code().is_none_or(|l| l == **c))
+        .filter_map(|c| escape_word(c))
+        .collect();
+    let (words, gaps, trailing) = split_words(text);
+    let normalized: Vec<String> = words.iter().map(|w| normalize(w)).collect();
+
+    let rule_at = |i: usize| {
+        rules.iter().find(|rule| {
+            normalized.len() - i >= rule.words.len()
+                && rule.words.iter().zip(&normalized[i..]).all(|(a, b)| a == b)
+        })
+    };
+    // Whether a phrase whose last word is `words[end - 1]` ends a sentence
+    let sentence_ends = |end: usize| {
+        end == words.len()
+            || words[end - 1].ends_with(ENGINE_PUNCTUATION)
+            || words[end].starts_with(char::is_uppercase)
+            || rule_at(end).is_some()
+    };
+
+    let mut out = String::new();
+    // Whether the next word must be glued to `out` without a space
+    let mut glue_next = true;
+    // Where the last spoken symbol ends in `out`: trimming the engine's
+    // punctuation stops there
+    let mut symbol_end = 0;
+    // Line breaks already in the text, or a space unless glued
+    let separate = |out: &mut String, gap: &str, glue: bool| {
+        let breaks: String = gap.chars().filter(|c| matches!(c, '\r' | '\n')).collect();
+        if !breaks.is_empty() {
+            out.push_str(&breaks);
+        } else if !glue {
+            out.push(' ');
+        }
+    };
+    let mut i = 0;
+    while i < words.len() {
+        let gap = gaps[i];
+        // "literal period" writes "period"
+        let escaped = escapes.contains(&normalized[i].as_str())
+            && i + 1 < words.len()
+            && rule_at(i + 1).is_some();
+        if escaped {
+            i += 1;
+        }
+        let matched = rule_at(i).filter(|rule| {
+            !escaped && (!rule.sentence_end_only || sentence_ends(i + rule.words.len()))
+        });
+
+        let Some(rule) = matched else {
+            // An escaped phrase is written out word for word
+            let len = if escaped { rule_at(i).map_or(1, |r| r.words.len()) } else { 1 };
+            for (k, word) in words[i..i + len].iter().enumerate() {
+                separate(&mut out, if k == 0 { gap } else { gaps[i + k] }, glue_next);
+                out.push_str(word);
+                glue_next = false;
+            }
+            i += len;
+            continue;
+        };
+
+        match rule.join {
+            // "Is it ok, question mark": the engine's comma is not wanted,
+            // but "question mark exclamation mark" keeps the "?"
+            Join::Previous => {
+                let kept = out.trim_end_matches(ENGINE_PUNCTUATION).len();
+                out.truncate(kept.max(symbol_end));
+            }
+            Join::Both => separate(&mut out, gap, true),
+            Join::Next | Join::Neither => separate(&mut out, gap, glue_next),
+        }
+        out.push_str(&rule.text);
+        symbol_end = out.len();
+        glue_next = matches!(rule.join, Join::Next | Join::Both);
+        i += rule.words.len();
+    }
+    if !words.is_empty() {
+        separate(&mut out, trailing, true);
+    }
+    out
+}
+
+/// Words of `text`, the whitespace before each, and the whitespace after
+/// the last one.
+fn split_words(text: &str) -> (Vec<&str>, Vec<&str>, &str) {
+    let mut words = Vec::new();
+    let mut gaps = Vec::new();
+    let mut rest = text;
+    loop {
+        let word_start = rest.trim_start();
+        let gap = &rest[..rest.len() - word_start.len()];
+        if word_start.is_empty() {
+            return (words, gaps, gap);
+        }
+        let end = word_start.find(char::is_whitespace).unwrap_or(word_start.len());
+        gaps.push(gap);
+        words.push(&word_start[..end]);
+        rest = &word_start[end..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn en(text: &str) -> String {
+        apply(text, &[], &Language::try_from("en".to_string()).unwrap())
+    }
+
+    #[test]
+    fn spoken_symbols_are_replaced() {
+        assert_eq!(en("hello comma world"), "hello, world");
+        assert_eq!(en("is it ok, question mark"), "is it ok?");
+        assert_eq!(en("note colon buy milk"), "note: buy milk");
+        assert_eq!(en("open parenthesis aside close parenthesis"), "(aside)");
+        assert_eq!(en("first new line second"), "first\nsecond");
+    }
+
+    #[test]
+    fn symbols_in_a_row_are_kept() {
+        assert_eq!(en("really question mark exclamation mark"), "really?!");
+        assert_eq!(en("wait, exclamation mark, question mark"), "wait!?");
+        assert_eq!(en("end quote period"), "\".");
+    }
+
+    #[test]
+    fn line_breaks_in_the_input_are_kept() {
+        assert_eq!(en("- milk comma\n- eggs\n"), "- milk,\n- eggs\n");
+        assert_eq!(en("Dear Sam comma\n\nthanks  period"), "Dear Sam,\n\nthanks.");
+        assert_eq!(en("one\r\ntwo"), "one\r\ntwo");
+    }
+
+    #[test]
+    fn period_only_ends_sentences() {
+        assert_eq!(en("the trial period ended"), "the trial period ended");
+        assert_eq!(en("that is all period"), "that is all.");
+        assert_eq!(en("done period. Next one"), "done. Next one");
+        assert_eq!(en("done period Next one"), "done. Next one");
+        assert_eq!(en("done period new line next"), "done.\nnext");
+    }
+
+    #[test]
+    fn literal_keeps_the_phrase() {
+        assert_eq!(en("type literal colon here"), "type colon here");
+        assert_eq!(en("say literal question mark"), "say question mark");
+        assert_eq!(en("a literal translation"), "a literal translation");
+    }
+
+    #[test]
+    fn french_uses_non_breaking_spaces() {
+        let fr = Language::try_from("fr".to_string()).unwrap();
+        assert_eq!(
+            apply("vraiment point d'interrogation", &[], &fr),
+            format!("vraiment{}?", NBSP)
+        );
+        assert_eq!(apply("littéralement virgule", &[], &fr), "virgule");
+    }
+
+    #[test]
+    fn user_rules_override_builtins() {
+        let rules = vec![PunctuationRule {
+            phrase: "period".to_string(),
+            text: "PERIOD".to_string(),
+            join: Join::Neither,
+            language: None,
+        }];
+        let en = Language::try_from("en".to_string()).unwrap();
+        assert_eq!(apply("the trial period ended", &rules, &en), "the trial PERIOD ended");
+    }
+}
@@ -1,12 +1,24 @@
 use std::sync::atomic::Ordering;
 use tauri::{AppHandle, Emitter, Manager};
-use tauri_plugin_global_shortcut::GlobalShortcutExt;
+use unicode_segmentation::UnicodeSegmentation;
 
 use crate::audio;
+use crate::config::{AppRule, Language, UndoMethod};
+use crate::dictionary;
+use crate::engine_loader;
+use crate::focus::{self, FocusTarget};
+use crate::history;
+use crate::output::{self, OutputSink};
 use crate::paste;
+use crate::postprocess::{self, Transform};
+use crate::shortcuts;
+use crate::snippets;
 use crate::state::AppState;
+use crate::stt::{TranscribeOptions, TranscriptionResult};
+use crate::voice;
+use crate::wayland;
 
-fn emit_error(app: &AppHandle, msg: &str) {
+pub fn emit_error(app: &AppHandle, msg: &str) {
     eprintln!("ERROR: {}", msg);
     let _ = app.emit("app-error", msg.to_string());
     // Show settings window so the user actually sees the toast
@@ -16,12 +28,110 @@ fn emit_error(app: &AppHandle, msg: &str) {
     }
 }
 
-fn register_escape(app: &AppHandle) {
-    let _ = app.global_shortcut().register("Escape");
+/// Per-recording overrides chosen by the shortcut that started it.
+#[derive(Debug, Clone, Default)]
+pub struct DictationMode {
+    /// Use this language instead of the profile's
+    pub language: Option<Language>,
+    pub translate: bool,
+    /// The dictation is a voice command, not text to paste
+    pub command: bool,
 }
 
-fn unregister_escape(app: &AppHandle) {
-    let _ = app.global_shortcut().unregister("Escape");
+/// Text inserted into an app by a paste or typing output, remembered so it
+/// can be undone.
+#[derive(Debug, Clone)]
+pub struct PastedText {
+    pub text: String,
+    /// Grapheme clusters, i.e. Backspaces needed to remove `text`: one
+    /// Backspace deletes "é" or "👍🏽" whole even when they are several
+    /// `char`s
+    pub graphemes: usize,
+    /// App the text was pasted into
+    pub target: FocusTarget,
+    /// Set by the app rule for `target`
+    pub undo_method: Option<UndoMethod>,
+}
+
+/// Run `samples` through the primary engine, then through the fallback engine
+/// if the primary crashed, failed or returned empty text.
+fn transcribe_with_fallback(
+    state: &AppState,
+    samples: &[f32],
+    options: &TranscribeOptions,
+) -> Result<TranscriptionResult, String> {
+    let cfg = state.config.lock().unwrap().clone();
+    let primary_engine = state.engine.lock().unwrap().kind();
+
+    // Transcribe in a catch_unwind to prevent hard crashes
+    let primary = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let mut engine = state.engine.lock().unwrap();
+        if !engine.is_loaded() {
+            if state.engine_loading.load(Ordering::SeqCst) {
+                return Err("Speech model is still loading — try again in a moment".to_string());
+            }
+            return Err("STT engine not loaded — download a model in Settings".to_string());
+        }
+        engine.transcribe(samples, options)
+    }));
+
+    let primary_error = match primary {
+        Ok(Ok(text)) if !text.is_empty() => {
+            return Ok(TranscriptionResult { text, engine: primary_engine, fallback: false });
+        }
+        Ok(Ok(_)) => None,
+        Ok(Err(e)) => Some(format!("Transcription failed: {}", e)),
+        Err(_) => {
+            // The engine is still usable for the next attempt; don't let the
+            // poisoned lock turn one crash into a permanent failure
+            state.engine.clear_poison();
+            Some("Transcription crashed — try a different STT engine or model".to_string())
+        }
+    };
+
+    let Some(fallback_engine) = cfg.fallback_engine else {
+        return match primary_error {
+            Some(e) => Err(e),
+            None => Ok(TranscriptionResult { text: String::new(), engine: primary_engine, fallback: false }),
+        };
+    };
+
+    eprintln!(
+        "Primary engine [{}]: {} — retrying with fallback [{}]",
+        primary_engine,
+        primary_error.as_deref().unwrap_or("no text"),
+        fallback_engine
+    );
+
+    let fallback = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let mut slot = state.fallback_engine.lock().unwrap();
+        // Lazy load on first use; a crashing engine is not put back
+        let mut engine = match slot.take() {
+            Some(engine) => engine,
+            None => engine_loader::load_engine(fallback_engine, cfg.fallback_model_size)?,
+        };
+        let text = engine.transcribe(samples, options);
+        *slot = Some(engine);
+        text
+    }));
+
+    let fallback_error = match fallback {
+        Ok(Ok(text)) => {
+            return Ok(TranscriptionResult { text, engine: fallback_engine, fallback: true });
+        }
+        Ok(Err(e)) => e,
+        Err(_) => {
+            state.fallback_engine.clear_poison();
+            "crashed".to_string()
+        }
+    };
+
+    Err(format!(
+        "{} (fallback engine {} also failed: {})",
+        primary_error.unwrap_or_else(|| "Transcription returned no text".to_string()),
+        fallback_engine,
+        fallback_error
+    ))
 }
 
 /// Stop recording and hide overlay without transcribing (ESC cancel).
@@ -33,105 +143,278 @@ pub fn cancel_recording(app: &AppHandle) {
 
     let _ = state.recorder.lock().unwrap().stop();
     let _ = app.emit("recording-stopped", ());
-    unregister_escape(app);
+    shortcuts::unregister_cancel(app);
 
     if let Some(window) = app.get_webview_window("recorder") {
         let _ = window.hide();
     }
 
     // Restore focus to the app that was active before recording
-    let pid = state.previous_app_pid.load(Ordering::SeqCst);
-    if pid > 0 {
-        paste::activate_pid(pid);
+    focus::activate(*state.previous_focus.lock().unwrap());
+}
+
+pub fn do_toggle_recording(app: &AppHandle, mode: DictationMode) {
+    let is_recording = app.state::<AppState>().recorder.lock().unwrap().is_recording();
+    if is_recording {
+        stop_recording(app);
+    } else {
+        start_recording(app, mode);
     }
 }
 
-pub fn do_toggle_recording(app: &AppHandle) {
+/// Stop recording, transcribe and paste the result. Does nothing if not
+/// recording, e.g. when a push-to-talk key is released after a cancel.
+pub fn stop_recording(app: &AppHandle) {
     let state = app.state::<AppState>();
-    let is_recording = state.recorder.lock().unwrap().is_recording();
+    if !state.recorder.lock().unwrap().is_recording() {
+        return;
+    }
 
-    if is_recording {
-        // Stop recording
-        let result = state.recorder.lock().unwrap().stop();
-        let _ = app.emit("recording-stopped", ());
-        unregister_escape(app);
-
-        // Hide overlay
-        if let Some(window) = app.get_webview_window("recorder") {
-            let _ = window.hide();
+    let result = state.recorder.lock().unwrap().stop();
+    let _ = app.emit("recording-stopped", ());
+    shortcuts::unregister_cancel(app);
+
+    // Hide overlay
+    if let Some(window) = app.get_webview_window("recorder") {
+        let _ = window.hide();
+    }
+
+    let (samples, sample_rate) = match result {
+        Ok(recorded) => recorded,
+        Err(e) => {
+            emit_error(app, &format!("Recording failed: {}", e));
+            return;
         }
+    };
 
-        match result {
-            Ok((samples, sample_rate)) => {
-                // Detect silent audio
-                let rms = if samples.is_empty() {
-                    0.0
-                } else {
-                    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
-                };
-                if rms < 1e-6 {
-                    let device = state.config.lock().unwrap().audio_device.clone();
-                    emit_error(app, &format!(
-                        "No audio detected (device: \"{}\"). Check that the device is connected, or grant microphone access in System Settings > Privacy & Security > Microphone",
-                        device
-                    ));
-                    return;
-                }
+    // Detect silent audio
+    let rms = if samples.is_empty() {
+        0.0
+    } else {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    };
+    if rms < 1e-6 {
+        let device = state.config.lock().unwrap().active().audio_device.clone();
+        emit_error(app, &format!(
+            "No audio detected (device: \"{}\"). Check that the device is connected, or grant microphone access in System Settings > Privacy & Security > Microphone",
+            device
+        ));
+        return;
+    }
 
-                let samples_16k = audio::resample(&samples, sample_rate, 16000);
+    let samples_16k = audio::resample(&samples, sample_rate, 16000);
 
-                let language = state.config.lock().unwrap().language.clone();
+    let cfg = state.config.lock().unwrap().clone();
+    let profile = cfg.active().clone();
+    let mode = state.dictation.lock().unwrap().clone();
+    let mut options = TranscribeOptions::from_config(&cfg);
+    if let Some(language) = mode.language {
+        options.language = language;
+    }
+    options.translate = mode.translate;
+    let rule = app_rule(app, *state.previous_focus.lock().unwrap());
 
-                // Transcribe in a catch_unwind to prevent hard crashes
-                let transcription = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                    let mut engine = state.engine.lock().unwrap();
-                    if !engine.is_loaded() {
-                        return Err("STT engine not loaded — download a model in Settings".to_string());
+    match transcribe_with_fallback(&state, &samples_16k, &options) {
+        Ok(mut result) => {
+            let commands = state.config.lock().unwrap().voice_commands.clone();
+            match voice::find(&commands, &result.text, mode.command) {
+                Ok(Some(command)) => {
+                    if let Err(e) = voice::run(app, command, &result.text) {
+                        emit_error(app, &e);
                     }
-                    engine.transcribe(&samples_16k, &language)
-                }));
-
-                match transcription {
-                    Ok(Ok(text)) => {
-                        if !text.is_empty() {
-                            // Restore focus to the app that was active before recording
-                            let pid = state.previous_app_pid.load(Ordering::SeqCst);
-                            if pid > 0 {
-                                paste::activate_pid(pid);
-                            }
-                            std::thread::sleep(std::time::Duration::from_millis(200));
-                            if let Err(e) = paste::paste_text(&text) {
-                                emit_error(app, &format!(
-                                    "Paste failed: {}. On macOS, enable Accessibility in System Settings > Privacy & Security > Accessibility",
-                                    e
-                                ));
-                            }
-                        }
+                    return;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    emit_error(app, &e);
+                    return;
+                }
+            }
+
+            let ctx = postprocess::Context {
+                language: &options.language,
+                profile: &profile.name,
+            };
+            let snippets = snippets::load().unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                Vec::new()
+            });
+            match snippets::find(&snippets, &result.text) {
+                // Templates are pasted exactly as written
+                Some(snippet) => result.text = snippets::expand(&snippet.template, &ctx),
+                None => {
+                    let chain = rule
+                        .as_ref()
+                        .and_then(|r| r.postprocess_profile.as_deref())
+                        .and_then(|name| cfg.profile(name))
+                        .map_or(&profile.postprocess, |p| &p.postprocess);
+                    result.text = postprocess::run(chain, &result.text, &ctx);
+                    match dictionary::load() {
+                        Ok(rules) => result.text = dictionary::apply(&rules, &result.text, &ctx),
+                        Err(e) => eprintln!("{}", e),
+                    }
+                    if rule.as_ref().is_some_and(|r| r.strip_trailing_period) {
+                        result.text = Transform::StripTrailingPeriod.apply(&result.text, &ctx);
                     }
-                    Ok(Err(e)) => emit_error(app, &format!("Transcription failed: {}", e)),
-                    Err(_) => emit_error(app, "Transcription crashed — try a different STT engine or model"),
                 }
             }
-            Err(e) => emit_error(app, &format!("Recording failed: {}", e)),
+            if result.text.is_empty() {
+                return;
+            }
+            if let Err(e) = history::record(&result, &options.language, &profile.name, &cfg.history) {
+                eprintln!("Failed to record history: {}", e);
+            }
+            *state.last_transcript.lock().unwrap() = Some(result.text.clone());
+            paste_into_previous_app(app, &result.text, rule.as_ref());
         }
-    } else {
-        // Start recording — capture frontmost app before showing overlay
-        let pid = paste::get_frontmost_pid();
-        state.previous_app_pid.store(pid, Ordering::SeqCst);
+        Err(e) => emit_error(app, &e),
+    }
+}
+
+/// Start recording unless already recording. `mode` applies to this
+/// recording only.
+pub fn start_recording(app: &AppHandle, mode: DictationMode) {
+    let state = app.state::<AppState>();
+    if state.recorder.lock().unwrap().is_recording() {
+        return;
+    }
 
-        let device = state.config.lock().unwrap().audio_device.clone();
+    // Nothing to transcribe with yet — say so instead of recording for nothing
+    if state.engine_loading.load(Ordering::SeqCst)
+        && matches!(state.engine.try_lock().map(|e| e.is_loaded()), Ok(false))
+    {
+        emit_error(app, "Speech model is still loading — try again in a moment");
+        return;
+    }
+
+    // Capture frontmost app before showing overlay
+    *state.previous_focus.lock().unwrap() = focus::frontmost();
+    *state.dictation.lock().unwrap() = mode;
 
-        // Show overlay
-        if let Some(window) = app.get_webview_window("recorder") {
-            let _ = window.show();
+    let device = state.config.lock().unwrap().active().audio_device.clone();
+
+    // Show overlay
+    if let Some(window) = app.get_webview_window("recorder") {
+        let _ = window.show();
+        // Wayland gives no way to hand focus back, so the overlay must not take it
+        if !wayland::is_session() {
             let _ = window.set_focus();
-            let _ = window.center();
         }
+        let _ = window.center();
+    }
 
-        if let Err(e) = state.recorder.lock().unwrap().start(&device, app.clone()) {
-            emit_error(app, &format!("Cannot start recording: {}", e));
-        } else {
-            register_escape(app);
-        }
+    if let Err(e) = state.recorder.lock().unwrap().start(&device, app.clone()) {
+        emit_error(app, &format!("Cannot start recording: {}", e));
+    } else {
+        shortcuts::register_cancel(app);
+    }
+}
+
+/// Paste the last transcription again into the frontmost app.
+pub fn repaste_last(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let Some(text) = state.last_transcript.lock().unwrap().clone() else {
+        return;
+    };
+    let target = focus::frontmost();
+    let rule = app_rule(app, target);
+    let mut sinks = output_sinks(app, rule.as_ref());
+    if let Err(e) = deliver(app, &mut sinks, &text, target, rule.as_ref()) {
+        emit_error(app, &format!("Output failed: {}", e));
+    }
+}
+
+/// The rule for dictations into the app behind `target`, if any.
+fn app_rule(app: &AppHandle, target: FocusTarget) -> Option<AppRule> {
+    let state = app.state::<AppState>();
+    // Skip the window system round trips when there is nothing to match
+    if state.config.lock().unwrap().app_rules.is_empty() {
+        return None;
+    }
+    let identity = focus::identify(target);
+    state.config.lock().unwrap().app_rule(&identity).cloned()
+}
+
+fn output_sinks(app: &AppHandle, rule: Option<&AppRule>) -> Vec<Box<dyn OutputSink>> {
+    let state = app.state::<AppState>();
+    let outputs = state.config.lock().unwrap().outputs.clone();
+    output::sinks(&outputs, rule)
+}
+
+/// Send `text` to `sinks`. When one of them put it into the frontmost app,
+/// `target`, remember it for [`undo_last`] along with `rule`, the app rule
+/// for `target`.
+fn deliver(
+    app: &AppHandle,
+    sinks: &mut [Box<dyn OutputSink>],
+    text: &str,
+    target: FocusTarget,
+    rule: Option<&AppRule>,
+) -> Result<(), String> {
+    let delivery = output::write_all(sinks, text);
+    if delivery.inserted {
+        *app.state::<AppState>().last_paste.lock().unwrap() = Some(PastedText {
+            text: text.to_string(),
+            graphemes: text.graphemes(true).count(),
+            target,
+            undo_method: rule.and_then(|r| r.undo_method),
+        });
+    }
+    if delivery.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(delivery.errors.join("; "))
+    }
+}
+
+/// Remove the text of the last paste from the app it went to, with
+/// Backspaces or the app's undo shortcut depending on the app rule or the
+/// config. Each paste can be undone once.
+pub fn undo_last(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let last = state
+        .last_paste
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or("Nothing to undo")?;
+
+    if last.target.is_known() && focus::frontmost() != last.target {
+        focus::activate(last.target);
+        std::thread::sleep(std::time::Duration::from_millis(200));
+    }
+    let method = last
+        .undo_method
+        .unwrap_or_else(|| state.config.lock().unwrap().undo_method);
+    let result = match method {
+        UndoMethod::Backspace => paste::press_backspaces(last.graphemes),
+        UndoMethod::UndoChord => paste::press_chord("CmdOrCtrl+Z"),
+    };
+    if result.is_err() {
+        // Nothing was removed, so the paste can still be undone
+        *state.last_paste.lock().unwrap() = Some(last);
+    }
+    result
+}
+
+/// Give focus back to the app that was active before recording.
+pub fn restore_focus(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    focus::activate(*state.previous_focus.lock().unwrap());
+    std::thread::sleep(std::time::Duration::from_millis(200));
+}
+
+fn paste_into_previous_app(app: &AppHandle, text: &str, rule: Option<&AppRule>) {
+    let mut sinks = output_sinks(app, rule);
+    // Files and pipes do not care which app has focus
+    if sinks.iter().any(|s| s.inserts_text()) {
+        restore_focus(app);
+    }
+    let target = *app.state::<AppState>().previous_focus.lock().unwrap();
+    if let Err(e) = deliver(app, &mut sinks, text, target, rule) {
+        emit_error(app, &format!(
+            "Output failed: {}. On macOS, enable Accessibility in System Settings > Privacy & Security > Accessibility",
+            e
+        ));
     }
 }
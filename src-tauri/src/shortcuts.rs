@@ -0,0 +1,521 @@
+use serde::Serialize;
+use std::fmt;
+use tauri::{AppHandle, Manager, Runtime};
+use tauri_plugin_global_shortcut::{
+    GlobalShortcut, GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState,
+};
+
+use crate::config::{Action, KeyBinding};
+use crate::recording::{self, DictationMode};
+use crate::state::{self, AppState};
+
+/// The action bound to `shortcut`, matched on the parsed key combination so
+/// `"alt+space"` and `"Alt+Space"` are the same binding.
+pub fn action_for(bindings: &[KeyBinding], shortcut: &Shortcut) -> Option<Action> {
+    bindings
+        .iter()
+        .find(|b| b.shortcut.parse::<Shortcut>().ok().as_ref() == Some(shortcut))
+        .map(|b| b.action.clone())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShortcutErrorKind {
+    /// The string is not a key combination
+    Invalid,
+    /// Another binding already uses the same key combination
+    Conflict,
+    /// The system refused the registration, usually because another
+    /// application holds the shortcut
+    Unavailable,
+    /// config.json could not be written
+    Save,
+}
+
+/// Why a set of bindings was rejected. Nothing is changed when one of these
+/// is returned.
+#[derive(Debug, Clone, Serialize)]
+pub struct ShortcutError {
+    pub kind: ShortcutErrorKind,
+    pub message: String,
+    /// The offending shortcut as written in the binding
+    pub shortcut: String,
+    /// Index of the offending binding
+    pub index: Option<usize>,
+    /// For conflicts, the action the shortcut is already bound to
+    pub conflicting_action: Option<Action>,
+}
+
+impl ShortcutError {
+    fn new(kind: ShortcutErrorKind, index: Option<usize>, shortcut: &str, message: String) -> Self {
+        Self {
+            kind,
+            message,
+            shortcut: shortcut.to_string(),
+            index,
+            conflicting_action: None,
+        }
+    }
+
+    /// A binding whose action is invalid, e.g. it targets a missing profile
+    pub fn invalid_binding(field: &str, message: &str) -> Self {
+        Self::new(
+            ShortcutErrorKind::Invalid,
+            None,
+            "",
+            format!("{}: {}", field, message),
+        )
+    }
+
+    pub fn save(message: String) -> Self {
+        Self::new(ShortcutErrorKind::Save, None, "", message)
+    }
+}
+
+impl fmt::Display for ShortcutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl From<ShortcutError> for String {
+    fn from(e: ShortcutError) -> Self {
+        e.message
+    }
+}
+
+/// Parse every binding and make sure no key combination is bound twice.
+/// Returns the parsed shortcuts in binding order.
+pub fn check(bindings: &[KeyBinding]) -> Result<Vec<Shortcut>, ShortcutError> {
+    let mut parsed: Vec<Shortcut> = Vec::with_capacity(bindings.len());
+    for (i, binding) in bindings.iter().enumerate() {
+        let shortcut = binding.shortcut.parse::<Shortcut>().map_err(|e| {
+            ShortcutError::new(
+                ShortcutErrorKind::Invalid,
+                Some(i),
+                &binding.shortcut,
+                format!("Invalid shortcut '{}': {}", binding.shortcut, e),
+            )
+        })?;
+
+        if let Some(other) = parsed.iter().position(|s| *s == shortcut) {
+            let mut error = ShortcutError::new(
+                ShortcutErrorKind::Conflict,
+                Some(i),
+                &binding.shortcut,
+                format!(
+                    "'{}' is already bound to {}",
+                    binding.shortcut,
+                    bindings[other].action.label()
+                ),
+            );
+            error.conflicting_action = Some(bindings[other].action.clone());
+            return Err(error);
+        }
+        parsed.push(shortcut);
+    }
+    Ok(parsed)
+}
+
+/// The system's table of global shortcuts. The registration logic below only
+/// goes through this, so it runs the same against a test double.
+trait Registry {
+    fn is_registered(&self, key: Shortcut) -> bool;
+    fn register(&self, key: Shortcut) -> Result<(), String>;
+    fn unregister(&self, key: Shortcut);
+}
+
+impl<R: Runtime> Registry for GlobalShortcut<R> {
+    fn is_registered(&self, key: Shortcut) -> bool {
+        GlobalShortcut::is_registered(self, key)
+    }
+
+    fn register(&self, key: Shortcut) -> Result<(), String> {
+        GlobalShortcut::register(self, key).map_err(|e| e.to_string())
+    }
+
+    fn unregister(&self, key: Shortcut) {
+        let _ = GlobalShortcut::unregister(self, key);
+    }
+}
+
+/// Register every binding except `Cancel`, which is only grabbed while
+/// recording so the key keeps working in other apps. Shortcuts that can't be
+/// registered are skipped and the first failure is reported.
+pub fn register_bindings(app: &AppHandle, bindings: &[KeyBinding]) -> Result<(), ShortcutError> {
+    register_all(app.global_shortcut(), bindings)
+}
+
+fn register_all(registry: &impl Registry, bindings: &[KeyBinding]) -> Result<(), ShortcutError> {
+    let mut first_error = None;
+    for (i, binding) in bindings.iter().enumerate() {
+        if binding.action == Action::Cancel {
+            continue;
+        }
+        let registered = binding
+            .shortcut
+            .parse::<Shortcut>()
+            .map_err(|e| e.to_string())
+            .and_then(|key| registry.register(key));
+        if let Err(e) = registered {
+            first_error.get_or_insert(ShortcutError::new(
+                ShortcutErrorKind::Unavailable,
+                Some(i),
+                &binding.shortcut,
+                format!("Cannot register '{}': {}", binding.shortcut, e),
+            ));
+        }
+    }
+    first_error.map_or(Ok(()), Err)
+}
+
+/// Shortcuts that are registered for as long as `bindings` is in effect.
+fn global_keys(bindings: &[KeyBinding]) -> Vec<Shortcut> {
+    bindings
+        .iter()
+        .filter(|b| b.action != Action::Cancel)
+        .filter_map(|b| b.shortcut.parse().ok())
+        .collect()
+}
+
+/// Switch the registered shortcuts from the `old` bindings to `new`.
+///
+/// All of `new` is validated before anything is touched, and shortcuts are
+/// registered before the old ones are released, so on any failure the
+/// previous set stays in effect.
+pub fn rebind(
+    app: &AppHandle,
+    old: &[KeyBinding],
+    new: &[KeyBinding],
+) -> Result<(), ShortcutError> {
+    switch_bindings(app.global_shortcut(), old, new)
+}
+
+fn switch_bindings(
+    registry: &impl Registry,
+    old: &[KeyBinding],
+    new: &[KeyBinding],
+) -> Result<(), ShortcutError> {
+    let parsed = check(new)?;
+    let old_keys = global_keys(old);
+
+    // A key registered outside `old_keys` is a `Cancel` grabbed for the
+    // recording in progress; from now on it belongs to the new binding
+    let mut added = Vec::new();
+    for (i, (binding, key)) in new.iter().zip(&parsed).enumerate() {
+        if binding.action == Action::Cancel
+            || old_keys.contains(key)
+            || registry.is_registered(*key)
+        {
+            continue;
+        }
+        if let Err(e) = registry.register(*key) {
+            for key in &added {
+                registry.unregister(*key);
+            }
+            return Err(ShortcutError::new(
+                ShortcutErrorKind::Unavailable,
+                Some(i),
+                &binding.shortcut,
+                format!("Cannot register '{}': {}", binding.shortcut, e),
+            ));
+        }
+        added.push(*key);
+    }
+
+    let new_keys = global_keys(new);
+    for key in old_keys {
+        if !new_keys.contains(&key) {
+            registry.unregister(key);
+        }
+    }
+    // `release_cancel` only knows the new bindings, so release a grabbed
+    // `Cancel` key that is no longer bound at all here
+    let new_cancel_keys = cancel_keys(new);
+    for key in cancel_keys(old) {
+        if !new_keys.contains(&key) && !new_cancel_keys.contains(&key) {
+            registry.unregister(key);
+        }
+    }
+    Ok(())
+}
+
+fn cancel_keys(bindings: &[KeyBinding]) -> Vec<Shortcut> {
+    bindings
+        .iter()
+        .filter(|b| b.action == Action::Cancel)
+        .filter_map(|b| b.shortcut.parse().ok())
+        .collect()
+}
+
+fn current_bindings(app: &AppHandle) -> Vec<KeyBinding> {
+    let state = app.state::<AppState>();
+    let bindings = state.config.lock().unwrap().bindings.clone();
+    bindings
+}
+
+/// Grab the `Cancel` shortcuts for the duration of a recording.
+pub fn register_cancel(app: &AppHandle) {
+    grab_cancel(app.global_shortcut(), &current_bindings(app));
+}
+
+fn grab_cancel(registry: &impl Registry, bindings: &[KeyBinding]) {
+    for key in cancel_keys(bindings) {
+        if !registry.is_registered(key) {
+            let _ = registry.register(key);
+        }
+    }
+}
+
+/// Release the `Cancel` shortcuts grabbed by [`register_cancel`]. Keys that
+/// were rebound to another action during the recording stay registered.
+pub fn unregister_cancel(app: &AppHandle) {
+    release_cancel(app.global_shortcut(), &current_bindings(app));
+}
+
+fn release_cancel(registry: &impl Registry, bindings: &[KeyBinding]) {
+    let global = global_keys(bindings);
+    for key in cancel_keys(bindings) {
+        if !global.contains(&key) {
+            registry.unregister(key);
+        }
+    }
+}
+
+/// Global shortcut handler: look up the binding and run its action.
+pub fn handle(app: &AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
+    let bindings = current_bindings(app);
+    let Some(action) = action_for(&bindings, shortcut) else {
+        return;
+    };
+
+    let app = app.clone();
+    match event.state {
+        ShortcutState::Pressed => {
+            std::thread::spawn(move || run_action(&app, action));
+        }
+        ShortcutState::Released => {
+            if action == Action::PushToTalk {
+                std::thread::spawn(move || recording::stop_recording(&app));
+            }
+        }
+    }
+}
+
+fn run_action(app: &AppHandle, action: Action) {
+    match action {
+        Action::Toggle => recording::do_toggle_recording(app, DictationMode::default()),
+        Action::PushToTalk => recording::start_recording(app, DictationMode::default()),
+        Action::Cancel => recording::cancel_recording(app),
+        Action::RepasteLast => recording::repaste_last(app),
+        Action::Translate => recording::do_toggle_recording(
+            app,
+            DictationMode {
+                translate: true,
+                ..Default::default()
+            },
+        ),
+        Action::DictateLanguage { language } => recording::do_toggle_recording(
+            app,
+            DictationMode {
+                language: Some(language),
+                ..Default::default()
+            },
+        ),
+        Action::Command => recording::do_toggle_recording(
+            app,
+            DictationMode {
+                command: true,
+                ..Default::default()
+            },
+        ),
+        Action::UndoLast => {
+            if let Err(e) = recording::undo_last(app) {
+                recording::emit_error(app, &e);
+            }
+        }
+        Action::SwitchProfile { profile } => {
+            if let Err(e) = state::switch_profile(app, &profile) {
+                recording::emit_error(app, &e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    /// Registry where `taken` keys are held by another application.
+    #[derive(Default)]
+    struct FakeRegistry {
+        registered: RefCell<Vec<Shortcut>>,
+        taken: Vec<Shortcut>,
+    }
+
+    impl FakeRegistry {
+        fn keys(&self) -> Vec<Shortcut> {
+            self.registered.borrow().clone()
+        }
+    }
+
+    impl Registry for FakeRegistry {
+        fn is_registered(&self, key: Shortcut) -> bool {
+            self.registered.borrow().contains(&key)
+        }
+
+        fn register(&self, key: Shortcut) -> Result<(), String> {
+            if self.taken.contains(&key) || self.is_registered(key) {
+                return Err("already registered".to_string());
+            }
+            self.registered.borrow_mut().push(key);
+            Ok(())
+        }
+
+        fn unregister(&self, key: Shortcut) {
+            self.registered.borrow_mut().retain(|k| *k != key);
+        }
+    }
+
+    fn bind(shortcut: &str, action: Action) -> KeyBinding {
+        KeyBinding::new(shortcut, action)
+    }
+
+    fn key(shortcut: &str) -> Shortcut {
+        shortcut.parse().unwrap()
+    }
+
+    #[test]
+    fn check_parses_in_binding_order() {
+        let bindings = [bind("Alt+Space", Action::Toggle), bind("Escape", Action::Cancel)];
+        assert_eq!(check(&bindings).unwrap(), [key("Alt+Space"), key("Escape")]);
+    }
+
+    #[test]
+    fn same_keys_written_differently_conflict() {
+        let bindings = [
+            bind("Alt+Space", Action::Toggle),
+            bind("Escape", Action::Cancel),
+            bind("alt+space", Action::Translate),
+        ];
+        let e = check(&bindings).unwrap_err();
+        assert_eq!(e.kind, ShortcutErrorKind::Conflict);
+        assert_eq!(e.index, Some(2));
+        assert_eq!(e.shortcut, "alt+space");
+        assert_eq!(e.conflicting_action, Some(Action::Toggle));
+    }
+
+    #[test]
+    fn unknown_keys_are_invalid() {
+        for shortcut in ["Alt+Nope", "", "Ctrl+"] {
+            let bindings = [bind("Escape", Action::Cancel), bind(shortcut, Action::Toggle)];
+            let e = check(&bindings).unwrap_err();
+            assert_eq!(e.kind, ShortcutErrorKind::Invalid, "{:?}", shortcut);
+            assert_eq!(e.index, Some(1));
+            assert_eq!(e.conflicting_action, None);
+        }
+    }
+
+    #[test]
+    fn each_key_maps_to_its_action() {
+        let french = Action::DictateLanguage {
+            language: "fr".to_string().try_into().unwrap(),
+        };
+        let bindings = [
+            bind("Alt+Space", Action::Toggle),
+            bind("Escape", Action::Cancel),
+            bind("Ctrl+Alt+F", french.clone()),
+        ];
+        assert_eq!(action_for(&bindings, &key("alt+space")), Some(Action::Toggle));
+        assert_eq!(action_for(&bindings, &key("Escape")), Some(Action::Cancel));
+        assert_eq!(action_for(&bindings, &key("Alt+Ctrl+F")), Some(french));
+        assert_eq!(action_for(&bindings, &key("Ctrl+Space")), None);
+    }
+
+    #[test]
+    fn cancel_is_only_grabbed_while_recording() {
+        let registry = FakeRegistry::default();
+        let bindings = [bind("Alt+Space", Action::Toggle), bind("Escape", Action::Cancel)];
+        register_all(&registry, &bindings).unwrap();
+        assert_eq!(registry.keys(), [key("Alt+Space")]);
+
+        grab_cancel(&registry, &bindings);
+        assert_eq!(registry.keys(), [key("Alt+Space"), key("Escape")]);
+        release_cancel(&registry, &bindings);
+        assert_eq!(registry.keys(), [key("Alt+Space")]);
+    }
+
+    #[test]
+    fn unavailable_keys_are_skipped_at_startup() {
+        let registry = FakeRegistry {
+            taken: vec![key("Alt+Space")],
+            ..Default::default()
+        };
+        let bindings = [bind("Alt+Space", Action::Toggle), bind("Ctrl+Alt+T", Action::Translate)];
+        let e = register_all(&registry, &bindings).unwrap_err();
+        assert_eq!(e.kind, ShortcutErrorKind::Unavailable);
+        assert_eq!(e.index, Some(0));
+        assert_eq!(registry.keys(), [key("Ctrl+Alt+T")]);
+    }
+
+    #[test]
+    fn rebind_swaps_the_registered_keys() {
+        let registry = FakeRegistry::default();
+        let old = [bind("Alt+Space", Action::Toggle), bind("Ctrl+Alt+T", Action::Translate)];
+        register_all(&registry, &old).unwrap();
+
+        let new = [bind("Ctrl+Alt+T", Action::Translate), bind("Super+D", Action::Toggle)];
+        switch_bindings(&registry, &old, &new).unwrap();
+        assert_eq!(registry.keys(), [key("Ctrl+Alt+T"), key("Super+D")]);
+    }
+
+    #[test]
+    fn failed_rebind_rolls_back() {
+        let registry = FakeRegistry {
+            taken: vec![key("Ctrl+Alt+T")],
+            ..Default::default()
+        };
+        let old = [bind("Alt+Space", Action::Toggle)];
+        register_all(&registry, &old).unwrap();
+
+        let new = [bind("Super+D", Action::Toggle), bind("Ctrl+Alt+T", Action::Translate)];
+        let e = switch_bindings(&registry, &old, &new).unwrap_err();
+        assert_eq!(e.kind, ShortcutErrorKind::Unavailable);
+        assert_eq!(e.index, Some(1));
+        assert_eq!(registry.keys(), [key("Alt+Space")]);
+    }
+
+    #[test]
+    fn conflicting_rebind_changes_nothing() {
+        let registry = FakeRegistry::default();
+        let old = [bind("Alt+Space", Action::Toggle)];
+        register_all(&registry, &old).unwrap();
+
+        let new = [bind("Super+D", Action::Toggle), bind("super+d", Action::Translate)];
+        let e = switch_bindings(&registry, &old, &new).unwrap_err();
+        assert_eq!(e.kind, ShortcutErrorKind::Conflict);
+        assert_eq!(registry.keys(), [key("Alt+Space")]);
+    }
+
+    #[test]
+    fn cancel_rebound_while_recording_is_kept_or_released() {
+        let registry = FakeRegistry::default();
+        let old = [bind("Alt+Space", Action::Toggle), bind("Escape", Action::Cancel)];
+        register_all(&registry, &old).unwrap();
+        grab_cancel(&registry, &old);
+
+        // Escape now toggles: it stays registered once the recording ends
+        let new = [bind("Escape", Action::Toggle), bind("Ctrl+Escape", Action::Cancel)];
+        switch_bindings(&registry, &old, &new).unwrap();
+        release_cancel(&registry, &new);
+        assert_eq!(registry.keys(), [key("Escape")]);
+
+        // Cancel unbound mid-recording: its grabbed key goes with it
+        grab_cancel(&registry, &new);
+        let unbound = [bind("Escape", Action::Toggle)];
+        switch_bindings(&registry, &new, &unbound).unwrap();
+        release_cancel(&registry, &unbound);
+        assert_eq!(registry.keys(), [key("Escape")]);
+    }
+}
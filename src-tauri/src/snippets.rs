@@ -0,0 +1,244 @@
+use arboard::Clipboard;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+use crate::config;
+use crate::postprocess::Context;
+use crate::text::normalize;
+
+/// Text pasted instead of the dictation when the dictation is `trigger`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snippet {
+    /// Assigned when the snippet is added; ignored on input
+    #[serde(default)]
+    pub id: u64,
+    /// Phrase to say, e.g. "insert signature". Matched loosely against the
+    /// whole dictation.
+    pub trigger: String,
+    /// Text to paste. `{date}`, `{time}`, `{clipboard}` and `{profile}` are
+    /// filled in when the snippet is used.
+    pub template: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SnippetsFile {
+    snippets: Vec<Snippet>,
+}
+
+/// Minimum similarity between the dictation and a trigger, from 0 (nothing
+/// in common) to 1 (same words). Leaves room for a misheard letter or two.
+const MATCH_THRESHOLD: f64 = 0.8;
+
+/// A `{name}` in a template
+static VARIABLE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{(\w+)\}").expect("valid regex"));
+
+pub fn snippets_path() -> PathBuf {
+    config::config_dir().join("snippets.json")
+}
+
+/// All snippets. A missing file means none.
+pub fn load() -> Result<Vec<Snippet>, String> {
+    let content = match fs::read_to_string(snippets_path()) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read snippets: {}", e)),
+    };
+    let file: SnippetsFile =
+        serde_json::from_str(&content).map_err(|e| format!("Invalid snippets.json: {}", e))?;
+    Ok(file.snippets)
+}
+
+fn save(snippets: &[Snippet]) -> Result<(), String> {
+    let file = SnippetsFile {
+        snippets: snippets.to_vec(),
+    };
+    let json =
+        serde_json::to_string_pretty(&file).map_err(|e| format!("Failed to serialize: {}", e))?;
+    config::write_atomic(&snippets_path(), json.as_bytes())
+}
+
+/// Edit distance in characters.
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein(&a, &b) as f64 / longest as f64
+}
+
+/// The snippet whose trigger is closest to `text`, if close enough.
+pub fn find<'a>(snippets: &'a [Snippet], text: &str) -> Option<&'a Snippet> {
+    let text = normalize(text);
+    if text.is_empty() {
+        return None;
+    }
+    snippets
+        .iter()
+        .map(|s| (s, similarity(&text, &normalize(&s.trigger))))
+        .filter(|(_, score)| *score >= MATCH_THRESHOLD)
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(s, _)| s)
+}
+
+/// Fill in the variables of `template`. Unknown `{names}` are kept as
+/// written.
+pub fn expand(template: &str, ctx: &Context) -> String {
+    expand_at(template, ctx, chrono::Local::now())
+}
+
+fn expand_at(template: &str, ctx: &Context, now: chrono::DateTime<chrono::Local>) -> String {
+    VARIABLE
+        .replace_all(template, |caps: &Captures| match &caps[1] {
+            "date" => now.format("%Y-%m-%d").to_string(),
+            "time" => now.format("%H:%M").to_string(),
+            "clipboard" => Clipboard::new()
+                .and_then(|mut c| c.get_text())
+                .unwrap_or_default(),
+            "profile" => ctx.profile.to_string(),
+            _ => caps[0].to_string(),
+        })
+        .into_owned()
+}
+
+impl Snippet {
+    fn validate(&self, others: &[Snippet]) -> Result<(), String> {
+        let trigger = normalize(&self.trigger);
+        if trigger.is_empty() {
+            return Err("The trigger phrase is empty".to_string());
+        }
+        if self.template.is_empty() {
+            return Err("The snippet text is empty".to_string());
+        }
+        match others
+            .iter()
+            .find(|s| s.id != self.id && normalize(&s.trigger) == trigger)
+        {
+            Some(other) => Err(format!(
+                "Another snippet is already triggered by `{}`",
+                other.trigger
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Append `snippet` with a fresh id and return it as stored.
+pub fn add(mut snippet: Snippet) -> Result<Snippet, String> {
+    let mut snippets = load()?;
+    snippet.id = snippets.iter().map(|s| s.id).max().unwrap_or(0) + 1;
+    snippet.validate(&snippets)?;
+    snippets.push(snippet.clone());
+    save(&snippets)?;
+    Ok(snippet)
+}
+
+/// Replace the snippet with the same id.
+pub fn update(snippet: Snippet) -> Result<(), String> {
+    let mut snippets = load()?;
+    snippet.validate(&snippets)?;
+    let slot = snippets
+        .iter_mut()
+        .find(|s| s.id == snippet.id)
+        .ok_or_else(|| format!("No snippet with id {}", snippet.id))?;
+    *slot = snippet;
+    save(&snippets)
+}
+
+pub fn delete(id: u64) -> Result<(), String> {
+    let mut snippets = load()?;
+    let before = snippets.len();
+    snippets.retain(|s| s.id != id);
+    if snippets.len() == before {
+        return Err(format!("No snippet with id {}", id));
+    }
+    save(&snippets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Language;
+    use chrono::TimeZone;
+
+    fn snippet(id: u64, trigger: &str) -> Snippet {
+        Snippet {
+            id,
+            trigger: trigger.to_string(),
+            template: String::new(),
+        }
+    }
+
+    fn distance(a: &str, b: &str) -> usize {
+        let a: Vec<char> = a.chars().collect();
+        let b: Vec<char> = b.chars().collect();
+        levenshtein(&a, &b)
+    }
+
+    #[test]
+    fn levenshtein_counts_character_edits() {
+        assert_eq!(distance("", ""), 0);
+        assert_eq!(distance("abc", ""), 3);
+        assert_eq!(distance("", "abc"), 3);
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("flaw", "lawn"), 2);
+        assert_eq!(distance("café", "cafe"), 1);
+    }
+
+    #[test]
+    fn find_tolerates_a_misheard_letter() {
+        let snippets = [snippet(1, "insert signature"), snippet(2, "insert address")];
+        assert_eq!(find(&snippets, "Insert signature.").map(|s| s.id), Some(1));
+        // 1 edit in 16 characters
+        assert_eq!(find(&snippets, "insert signatures").map(|s| s.id), Some(1));
+        assert_eq!(find(&snippets, "insert adress").map(|s| s.id), Some(2));
+    }
+
+    #[test]
+    fn find_compares_the_whole_dictation_to_the_threshold() {
+        let snippets = [snippet(1, "insert signature"), snippet(2, "my email")];
+        // 4 edits in 16 characters: similarity 0.75
+        assert_eq!(find(&snippets, "insert signal"), None);
+        assert_eq!(find(&snippets, "please insert signature here"), None);
+        // 2 edits in 8 characters: similarity 0.75
+        assert_eq!(find(&snippets, "by mail"), None);
+        // 2 edits in 10 characters is just enough
+        assert_eq!(find(&snippets, "my e-mails").map(|s| s.id), Some(2));
+        assert_eq!(find(&snippets, "..."), None);
+    }
+
+    #[test]
+    fn expand_fills_known_variables() {
+        let ctx = Context {
+            language: &Language::auto(),
+            profile: "Work",
+        };
+        let now = chrono::Local.with_ymd_and_hms(2024, 3, 7, 9, 5, 0).unwrap();
+        assert_eq!(
+            expand_at("{date} {time} from {profile}, {name} {}", &ctx, now),
+            "2024-03-07 09:05 from Work, {name} {}"
+        );
+    }
+}
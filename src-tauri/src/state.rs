@@ -1,23 +1,104 @@
 use std::path::PathBuf;
-use std::sync::atomic::AtomicI32;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
 
 use crate::audio::AudioRecorder;
-use crate::config::AppConfig;
+use crate::config::{self, AppConfig, Engine, ModelSize};
+use crate::engine_loader;
+use crate::focus::FocusTarget;
 use crate::model_manager;
+use crate::recording::{DictationMode, PastedText};
 use crate::stt::SttEngine;
+use crate::tray;
 
 pub struct AppState {
     pub recorder: Mutex<AudioRecorder>,
     pub engine: Mutex<SttEngine>,
+    /// Fallback engine, `None` until loaded (eagerly or on first failure)
+    pub fallback_engine: Mutex<Option<SttEngine>>,
     pub config: Mutex<AppConfig>,
-    /// PID of the app that was focused before recording started
-    pub previous_app_pid: AtomicI32,
+    /// App that was focused before recording started
+    pub previous_focus: Mutex<FocusTarget>,
+    /// True while a background model load is in flight
+    pub engine_loading: AtomicBool,
+    /// Bumped on every load request so stale loads never overwrite newer ones
+    pub engine_generation: AtomicU64,
+    /// Overrides for the recording in progress
+    pub dictation: Mutex<DictationMode>,
+    /// Last pasted transcription, for re-paste
+    pub last_transcript: Mutex<Option<String>>,
+    /// What the last paste inserted and where, for undo
+    pub last_paste: Mutex<Option<PastedText>>,
+    /// Problems found at startup, kept until the settings page is listening
+    /// and asks for them
+    pub startup_notices: Mutex<Vec<String>>,
 }
 
-pub fn get_model_path_for_config(cfg: &AppConfig) -> PathBuf {
-    match cfg.engine.as_str() {
-        "parakeet" => model_manager::parakeet_model_dir(),
-        _ => model_manager::whisper_model_path(&cfg.model_size),
+/// Bring the running app in line with a config that has just replaced
+/// `old` in `AppState`: reload engines whose settings changed. Shortcuts
+/// are rebound by the caller before the config is saved, so a rejected
+/// shortcut is never persisted. The audio device is read when each
+/// recording starts, so a device change takes effect on the next recording.
+/// Emits `config-changed` so open windows can refresh.
+pub fn apply_config_change(app: &AppHandle, old: &AppConfig, new: &AppConfig) {
+    let state = app.state::<AppState>();
+
+    let fallback_changed = old.fallback_engine != new.fallback_engine
+        || old.fallback_model_size != new.fallback_model_size
+        || old.fallback_eager != new.fallback_eager;
+    if fallback_changed {
+        engine_loader::spawn_fallback_load(app, new);
+    }
+
+    // Reload engine in the background if engine type or model size changed.
+    // The current engine stays usable until the new one is ready.
+    let (old_profile, new_profile) = (old.active(), new.active());
+    let engine_changed = old_profile.engine != new_profile.engine;
+    let model_changed =
+        old_profile.model_size != new_profile.model_size && new_profile.engine == Engine::Whisper;
+    // A busy engine is mid-transcription, hence loaded
+    let needs_load = !state.engine_loading.load(Ordering::SeqCst)
+        && matches!(state.engine.try_lock().map(|e| e.is_loaded()), Ok(false));
+    if engine_changed || model_changed || needs_load {
+        engine_loader::spawn_load(app, new_profile.engine, new_profile.model_size);
+    }
+
+    if old.profiles != new.profiles || old.active_profile != new.active_profile {
+        tray::refresh_menu(app);
+    }
+    let _ = app.emit("config-changed", ());
+}
+
+/// Make `name` the active profile, persist it and reload the engine if the
+/// profile uses a different one.
+pub fn switch_profile(app: &AppHandle, name: &str) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let (old, new) = {
+        let mut current = state.config.lock().unwrap();
+        if current.profile(name).is_none() {
+            return Err(format!("No profile named '{}'", name));
+        }
+        if current.active_profile == name {
+            return Ok(());
+        }
+
+        let mut new = current.clone();
+        new.active_profile = name.to_string();
+        config::save_config_to_disk(&new)?;
+        (std::mem::replace(&mut *current, new.clone()), new)
+    };
+
+    eprintln!("Switched to profile '{}'", name);
+    apply_config_change(app, &old, &new);
+    Ok(())
+}
+
+pub fn get_model_path(engine: Engine, model_size: ModelSize) -> PathBuf {
+    match engine {
+        Engine::Parakeet => model_manager::parakeet_model_dir(),
+        Engine::Whisper => model_manager::whisper_model_path(model_size),
+        // Runs on a server; there is no local model
+        Engine::Remote => PathBuf::new(),
     }
 }
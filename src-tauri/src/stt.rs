@@ -1,8 +1,43 @@
 use parakeet_rs::Transcriber;
+use reqwest::header::CONTENT_TYPE;
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
 
+use crate::audio;
+use crate::config::{AppConfig, Engine, Language, RemoteServer};
+use crate::hallucination::{self, HallucinationFilter, Segment};
+
+/// Decoding options for a single recording.
+#[derive(Debug, Clone)]
+pub struct TranscribeOptions {
+    pub language: Language,
+    /// Whisper initial prompt; empty for none
+    pub prompt: String,
+    /// Translate the speech to English instead of transcribing it (Whisper and
+    /// remote only)
+    pub translate: bool,
+    pub hallucination_filter: HallucinationFilter,
+    /// Server used by the remote engine
+    pub remote: RemoteServer,
+}
+
+impl TranscribeOptions {
+    /// Options of the active profile.
+    pub fn from_config(cfg: &AppConfig) -> Self {
+        let profile = cfg.active();
+        Self {
+            language: profile.language.clone(),
+            prompt: profile.prompt.clone(),
+            translate: false,
+            hallucination_filter: profile.hallucination_filter.clone(),
+            remote: cfg.remote.clone(),
+        }
+    }
+}
+
 pub struct WhisperEngine {
     ctx: Option<Arc<Mutex<WhisperContext>>>,
 }
@@ -35,7 +70,7 @@ impl WhisperEngine {
         self.ctx.is_some()
     }
 
-    pub fn transcribe(&self, samples: &[f32], language: &str) -> Result<String, String> {
+    pub fn transcribe(&self, samples: &[f32], options: &TranscribeOptions) -> Result<String, String> {
         let ctx = self.ctx.as_ref().ok_or("Whisper model not loaded")?;
         let ctx = ctx.lock().map_err(|e| format!("Lock error: {}", e))?;
 
@@ -45,9 +80,13 @@ impl WhisperEngine {
 
         let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
 
-        if language != "auto" {
-            params.set_language(Some(language));
+        if let Some(code) = options.language.code() {
+            params.set_language(Some(code));
         }
+        if !options.prompt.is_empty() {
+            params.set_initial_prompt(&options.prompt);
+        }
+        params.set_translate(options.translate);
 
         params.set_print_special(false);
         params.set_print_progress(false);
@@ -62,16 +101,34 @@ impl WhisperEngine {
             .map_err(|e| format!("Transcription failed: {}", e))?;
 
         let num_segments = state.full_n_segments();
+        let eot = ctx.token_eot();
 
-        let mut text = String::new();
+        let mut segments = Vec::new();
         for i in 0..num_segments {
             if let Some(segment) = state.get_segment(i) {
                 if let Ok(s) = segment.to_str() {
-                    text.push_str(s);
+                    // Timestamps and other special tokens come after EOT
+                    let logprobs: Vec<f32> = (0..segment.n_tokens())
+                        .filter_map(|t| segment.get_token(t))
+                        .map(|t| t.token_data())
+                        .filter(|d| d.id < eot)
+                        .map(|d| d.plog)
+                        .collect();
+                    segments.push(Segment {
+                        text: s.to_string(),
+                        no_speech_prob: segment.no_speech_probability(),
+                        avg_logprob: logprobs.iter().sum::<f32>() / logprobs.len().max(1) as f32,
+                    });
                 }
             }
         }
+        if options.hallucination_filter.enabled {
+            let (kept, dropped) = hallucination::filter(segments, &options.hallucination_filter);
+            hallucination::log(&dropped, &options.hallucination_filter);
+            segments = kept;
+        }
 
+        let text: String = segments.iter().map(|s| s.text.as_str()).collect();
         Ok(text.trim().to_string())
     }
 }
@@ -104,8 +161,12 @@ impl ParakeetEngine {
         self.model.is_some()
     }
 
-    /// Transcribe 16kHz mono f32 samples. Language param is ignored (Parakeet v3 auto-detects).
-    pub fn transcribe(&mut self, samples: &[f32], _language: &str) -> Result<String, String> {
+    /// Transcribe 16kHz mono f32 samples. Language and prompt are ignored
+    /// (Parakeet v3 auto-detects the language); translation is unsupported.
+    pub fn transcribe(&mut self, samples: &[f32], options: &TranscribeOptions) -> Result<String, String> {
+        if options.translate {
+            return Err("Parakeet cannot translate — use a Whisper profile".to_string());
+        }
         let model = self.model.as_mut().ok_or("Parakeet model not loaded")?;
 
         let result = model
@@ -116,11 +177,152 @@ impl ParakeetEngine {
     }
 }
 
+/// Sends recordings to an OpenAI-compatible `/v1/audio/transcriptions`
+/// endpoint. Holds no state: the server settings come with each request.
+pub struct RemoteEngine;
+
+#[derive(Deserialize)]
+struct RemoteTranscription {
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct RemoteErrorBody {
+    error: RemoteErrorDetail,
+}
+
+#[derive(Deserialize)]
+struct RemoteErrorDetail {
+    message: String,
+}
+
+impl RemoteEngine {
+    /// Transcribe 16kHz mono f32 samples. Translation goes to the sibling
+    /// `/translations` endpoint.
+    pub fn transcribe(&self, samples: &[f32], options: &TranscribeOptions) -> Result<String, String> {
+        let server = &options.remote;
+        if server.url.trim().is_empty() {
+            return Err("No transcription server configured — set one in Settings".to_string());
+        }
+        let url = if options.translate {
+            match server.url.trim_end_matches('/').strip_suffix("/transcriptions") {
+                Some(base) => format!("{}/translations", base),
+                None => {
+                    return Err(format!(
+                        "Cannot translate: {} is not a /transcriptions endpoint",
+                        server.url
+                    ))
+                }
+            }
+        } else {
+            server.url.clone()
+        };
+
+        let mut fields = vec![("model", server.model.as_str()), ("response_format", "json")];
+        // Translations are always to English; the source language is detected
+        if let (Some(code), false) = (options.language.code(), options.translate) {
+            fields.push(("language", code));
+        }
+        if !options.prompt.is_empty() {
+            fields.push(("prompt", options.prompt.as_str()));
+        }
+        let boundary = format!(
+            "lightwhisper-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_nanos())
+                .unwrap_or(0)
+        );
+        let body = multipart_body(&boundary, &fields, &audio::encode_wav(samples, 16000));
+
+        let client = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(server.timeout_secs))
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+        let mut request = client
+            .post(&url)
+            .header(CONTENT_TYPE, format!("multipart/form-data; boundary={}", boundary))
+            .body(body);
+        if !server.api_key.is_empty() {
+            request = request.bearer_auth(&server.api_key);
+        }
+
+        let response = request.send().map_err(|e| {
+            if e.is_timeout() {
+                format!(
+                    "Transcription server at {} did not answer within {}s",
+                    url, server.timeout_secs
+                )
+            } else if e.is_connect() {
+                format!("Cannot reach transcription server at {}", url)
+            } else {
+                format!("Request to {} failed: {}", url, e)
+            }
+        })?;
+        let status = response.status();
+        let body = response
+            .text()
+            .map_err(|e| format!("Failed to read response from {}: {}", url, e))?;
+
+        if !status.is_success() {
+            // OpenAI-style servers explain the failure in `error.message`
+            let detail = serde_json::from_str::<RemoteErrorBody>(&body)
+                .map(|b| b.error.message)
+                .unwrap_or_else(|_| body.trim().to_string());
+            return Err(match status.as_u16() {
+                401 | 403 => format!("Transcription server rejected the API key: {}", detail),
+                404 => format!("No transcription endpoint at {}", url),
+                _ => format!("Transcription server answered {}: {}", status, detail),
+            });
+        }
+
+        let result: RemoteTranscription = serde_json::from_str(&body)
+            .map_err(|e| format!("Invalid response from transcription server: {}", e))?;
+        Ok(result.text.trim().to_string())
+    }
+}
+
+/// A `multipart/form-data` body with text `fields` and the recording as
+/// `file`. Built in memory: a streamed body hides connection errors.
+fn multipart_body(boundary: &str, fields: &[(&str, &str)], wav: &[u8]) -> Vec<u8> {
+    let mut body = Vec::with_capacity(wav.len() + 1024);
+    for (name, value) in fields {
+        body.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                boundary, name, value
+            )
+            .as_bytes(),
+        );
+    }
+    body.extend_from_slice(
+        format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"audio.wav\"\r\n\
+             Content-Type: audio/wav\r\n\r\n",
+            boundary
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(wav);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+    body
+}
+
 // ── Unified Engine ──
 
+/// Text produced for one recording, tagged with the engine that produced it.
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptionResult {
+    pub text: String,
+    pub engine: Engine,
+    /// True when the primary engine failed and the fallback produced `text`
+    pub fallback: bool,
+}
+
 enum EngineInner {
     Whisper(WhisperEngine),
     Parakeet(ParakeetEngine),
+    Remote(RemoteEngine),
 }
 
 pub struct SttEngine {
@@ -140,10 +342,27 @@ impl SttEngine {
         }
     }
 
-    pub fn from_engine_name(name: &str) -> Self {
-        match name {
-            "parakeet" => Self::new_parakeet(),
-            _ => Self::new_whisper(),
+    pub fn new_remote() -> Self {
+        Self {
+            inner: EngineInner::Remote(RemoteEngine),
+        }
+    }
+
+    pub fn new(engine: Engine) -> Self {
+        match engine {
+            Engine::Whisper => Self::new_whisper(),
+            Engine::Parakeet => Self::new_parakeet(),
+            Engine::Remote => Self::new_remote(),
+        }
+    }
+
+    /// Which engine this is. May differ from the configured one while a
+    /// newly configured engine is still loading.
+    pub fn kind(&self) -> Engine {
+        match &self.inner {
+            EngineInner::Whisper(_) => Engine::Whisper,
+            EngineInner::Parakeet(_) => Engine::Parakeet,
+            EngineInner::Remote(_) => Engine::Remote,
         }
     }
 
@@ -151,6 +370,8 @@ impl SttEngine {
         match &mut self.inner {
             EngineInner::Whisper(w) => w.load_model(path),
             EngineInner::Parakeet(p) => p.load_model(path),
+            // Nothing to load; the server is checked when a recording is sent
+            EngineInner::Remote(_) => Ok(()),
         }
     }
 
@@ -158,13 +379,148 @@ impl SttEngine {
         match &self.inner {
             EngineInner::Whisper(w) => w.is_loaded(),
             EngineInner::Parakeet(p) => p.is_loaded(),
+            EngineInner::Remote(_) => true,
         }
     }
 
-    pub fn transcribe(&mut self, samples: &[f32], language: &str) -> Result<String, String> {
+    pub fn transcribe(&mut self, samples: &[f32], options: &TranscribeOptions) -> Result<String, String> {
         match &mut self.inner {
-            EngineInner::Whisper(w) => w.transcribe(samples, language),
-            EngineInner::Parakeet(p) => p.transcribe(samples, language),
+            EngineInner::Whisper(w) => w.transcribe(samples, options),
+            EngineInner::Parakeet(p) => p.transcribe(samples, options),
+            EngineInner::Remote(r) => r.transcribe(samples, options),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{closed_url, Stub};
+
+    const PATH: &str = "/v1/audio/transcriptions";
+
+    fn options(url: String, language: &str) -> TranscribeOptions {
+        TranscribeOptions {
+            language: Language::try_from(language.to_string()).unwrap(),
+            prompt: String::new(),
+            translate: false,
+            hallucination_filter: HallucinationFilter::default(),
+            remote: RemoteServer {
+                url,
+                api_key: "secret".to_string(),
+                timeout_secs: 5,
+                ..RemoteServer::default()
+            },
         }
     }
+
+    /// Value of the form field `name` in a multipart body.
+    fn field(body: &str, name: &str) -> Option<String> {
+        let start = body.find(&format!("name=\"{}\"\r\n\r\n", name))?;
+        let value = &body[start..];
+        let value = &value[value.find("\r\n\r\n")? + 4..];
+        Some(value[..value.find("\r\n")?].to_string())
+    }
+
+    fn transcribe(options: &TranscribeOptions) -> Result<String, String> {
+        RemoteEngine.transcribe(&[0.0; 1600], options)
+    }
+
+    #[test]
+    fn sends_the_recording_with_language_and_prompt() {
+        let server = Stub::answer("200 OK", r#"{"text":" Hello world. "}"#);
+        let mut options = options(server.url(PATH), "fr");
+        options.prompt = "Light Whisper".to_string();
+        assert_eq!(transcribe(&options).unwrap(), "Hello world.");
+
+        let request = server.request();
+        assert!(request.head.starts_with("POST /v1/audio/transcriptions "));
+        assert_eq!(request.header("authorization"), Some("Bearer secret"));
+        assert!(request
+            .header("content-type")
+            .unwrap()
+            .starts_with("multipart/form-data; boundary=lightwhisper-"));
+        let body = request.body_text();
+        assert_eq!(field(&body, "model").as_deref(), Some("whisper-1"));
+        assert_eq!(field(&body, "response_format").as_deref(), Some("json"));
+        assert_eq!(field(&body, "language").as_deref(), Some("fr"));
+        assert_eq!(field(&body, "prompt").as_deref(), Some("Light Whisper"));
+        assert!(body.contains("filename=\"audio.wav\"\r\nContent-Type: audio/wav\r\n\r\nRIFF"));
+    }
+
+    #[test]
+    fn auto_detection_sends_no_language() {
+        let server = Stub::answer("200 OK", r#"{"text":"hi"}"#);
+        transcribe(&options(server.url(PATH), "auto")).unwrap();
+        let body = server.request().body_text();
+        assert_eq!(field(&body, "language"), None);
+        assert_eq!(field(&body, "prompt"), None);
+    }
+
+    #[test]
+    fn translation_goes_to_the_translations_endpoint() {
+        let server = Stub::answer("200 OK", r#"{"text":"hi"}"#);
+        let mut options = options(server.url("/v1/audio/transcriptions/"), "fr");
+        options.translate = true;
+        transcribe(&options).unwrap();
+
+        let request = server.request();
+        assert!(request.head.starts_with("POST /v1/audio/translations "));
+        assert_eq!(field(&request.body_text(), "language"), None);
+
+        options.remote.url = "http://127.0.0.1:1/v1/audio/speech".to_string();
+        let err = transcribe(&options).unwrap_err();
+        assert!(err.starts_with("Cannot translate:"), "{}", err);
+    }
+
+    #[test]
+    fn error_statuses_are_explained() {
+        let server = Stub::answer(
+            "401 Unauthorized",
+            r#"{"error":{"message":"Incorrect API key provided"}}"#,
+        );
+        let err = transcribe(&options(server.url(PATH), "en")).unwrap_err();
+        assert_eq!(
+            err,
+            "Transcription server rejected the API key: Incorrect API key provided"
+        );
+
+        let server = Stub::answer("404 Not Found", "");
+        let url = server.url(PATH);
+        let err = transcribe(&options(url.clone(), "en")).unwrap_err();
+        assert_eq!(err, format!("No transcription endpoint at {}", url));
+
+        let server = Stub::answer("503 Service Unavailable", "model loading");
+        let err = transcribe(&options(server.url(PATH), "en")).unwrap_err();
+        assert_eq!(
+            err,
+            "Transcription server answered 503 Service Unavailable: model loading"
+        );
+    }
+
+    #[test]
+    fn unreachable_servers_are_explained() {
+        let url = closed_url(PATH);
+        let err = transcribe(&options(url.clone(), "en")).unwrap_err();
+        assert_eq!(err, format!("Cannot reach transcription server at {}", url));
+
+        let server = Stub::silent(Duration::from_secs(2));
+        let mut options = options(server.url(PATH), "en");
+        options.remote.timeout_secs = 1;
+        let err = transcribe(&options).unwrap_err();
+        assert_eq!(
+            err,
+            format!(
+                "Transcription server at {} did not answer within 1s",
+                options.remote.url
+            )
+        );
+        server.request();
+    }
+
+    #[test]
+    fn missing_url_is_reported() {
+        let err = transcribe(&options(String::new(), "en")).unwrap_err();
+        assert!(err.starts_with("No transcription server configured"));
+    }
 }
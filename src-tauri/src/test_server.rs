@@ -0,0 +1,108 @@
+//! One-shot HTTP server on 127.0.0.1 for testing the HTTP clients.
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// A request as the server received it.
+pub struct Request {
+    /// Request line and headers
+    pub head: String,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.head
+            .lines()
+            .filter_map(|l| l.split_once(':'))
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.trim())
+    }
+
+    pub fn body_text(&self) -> String {
+        String::from_utf8_lossy(&self.body).to_string()
+    }
+}
+
+/// Serves a single connection on a free port.
+pub struct Stub {
+    addr: String,
+    handle: JoinHandle<Request>,
+}
+
+impl Stub {
+    /// Answer the request with `status`, e.g. `"404 Not Found"`, and a JSON
+    /// `body`.
+    pub fn answer(status: &'static str, body: impl Into<String>) -> Self {
+        let body = body.into();
+        Self::spawn(move |stream| {
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+                 Connection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        })
+    }
+
+    /// Read the request and keep the connection open for `hold` without
+    /// answering.
+    pub fn silent(hold: Duration) -> Self {
+        Self::spawn(move |_| thread::sleep(hold))
+    }
+
+    fn spawn(respond: impl FnOnce(&mut TcpStream) + Send + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let request = read_request(&mut stream);
+            respond(&mut stream);
+            request
+        });
+        Self { addr, handle }
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{}", self.addr, path)
+    }
+
+    /// The request the server got, once it has answered.
+    pub fn request(self) -> Request {
+        self.handle.join().unwrap()
+    }
+}
+
+/// URL of a port nothing listens on: port 1 is reserved (tcpmux) and only
+/// root could bind it.
+pub fn closed_url(path: &str) -> String {
+    format!("http://127.0.0.1:1{}", path)
+}
+
+fn read_request(stream: &mut TcpStream) -> Request {
+    let mut data = Vec::new();
+    let mut buf = [0; 8192];
+    loop {
+        let n = stream.read(&mut buf).unwrap();
+        data.extend_from_slice(&buf[..n]);
+        if let Some(end) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            let mut request = Request {
+                head: String::from_utf8_lossy(&data[..end]).to_string(),
+                body: Vec::new(),
+            };
+            let length = request
+                .header("content-length")
+                .map_or(0, |v| v.parse().unwrap());
+            if data.len() >= end + 4 + length || n == 0 {
+                request.body = data[end + 4..].to_vec();
+                return request;
+            }
+        } else if n == 0 {
+            panic!("connection closed before the end of the request");
+        }
+    }
+}
@@ -0,0 +1,23 @@
+/// Comparable form of a phrase: lower case words without punctuation, so
+/// "Insert signature." matches "insert signature".
+pub fn normalize(text: &str) -> String {
+    text.to_lowercase()
+        .replace('’', "'")
+        .split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_keeps_only_lower_case_words() {
+        assert_eq!(normalize("  Insert signature. "), "insert signature");
+        assert_eq!(normalize("Don’t-stop, NOW!"), "don't stop now");
+        assert_eq!(normalize("Ça va?"), "ça va");
+        assert_eq!(normalize("..."), "");
+    }
+}
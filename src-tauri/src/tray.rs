@@ -1,17 +1,48 @@
 use tauri::{
-    menu::{Menu, MenuItem},
+    menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu},
     tray::TrayIconBuilder,
-    AppHandle, Manager,
+    AppHandle, Manager, Wry,
 };
 
-pub fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+use crate::recording;
+use crate::state::{self, AppState};
+
+const TRAY_ID: &str = "main";
+
+/// Menu ids of profile entries are this prefix followed by the profile name
+const PROFILE_ID_PREFIX: &str = "profile:";
+
+fn build_menu(app: &AppHandle) -> tauri::Result<Menu<Wry>> {
+    let cfg = app.state::<AppState>().config.lock().unwrap().clone();
+
+    let profiles_menu = Submenu::new(app, "Profile", true)?;
+    for profile in &cfg.profiles {
+        let item = CheckMenuItem::with_id(
+            app,
+            format!("{}{}", PROFILE_ID_PREFIX, profile.name),
+            &profile.name,
+            true,
+            profile.name == cfg.active_profile,
+            None::<&str>,
+        )?;
+        profiles_menu.append(&item)?;
+    }
+
+    let separator = PredefinedMenuItem::separator(app)?;
     let settings_item = MenuItem::with_id(app, "settings", "Settings", true, None::<&str>)?;
     let quit_item = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
-    let menu = Menu::with_items(app, &[&settings_item, &quit_item])?;
+    Menu::with_items(
+        app,
+        &[&profiles_menu, &separator, &settings_item, &quit_item],
+    )
+}
+
+pub fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let menu = build_menu(app)?;
 
     let icon = tauri::image::Image::from_bytes(include_bytes!("../icons/tray-icon@2x.png"))?;
 
-    let _tray = TrayIconBuilder::new()
+    let _tray = TrayIconBuilder::with_id(TRAY_ID)
         .icon(icon)
         .icon_as_template(true)
         .menu(&menu)
@@ -28,9 +59,36 @@ pub fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
             "quit" => {
                 app.exit(0);
             }
-            _ => {}
+            id => {
+                if let Some(name) = id.strip_prefix(PROFILE_ID_PREFIX) {
+                    let app = app.clone();
+                    let name = name.to_string();
+                    std::thread::spawn(move || {
+                        let result = state::switch_profile(&app, &name);
+                        // The click toggled the check mark itself; redraw it
+                        // from the config, also when nothing changed
+                        refresh_menu(&app);
+                        if let Err(e) = result {
+                            recording::emit_error(&app, &e);
+                        }
+                    });
+                }
+            }
         })
         .build(app)?;
 
     Ok(())
 }
+
+/// Rebuild the tray menu, e.g. after profiles were added, renamed or switched.
+pub fn refresh_menu(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    match build_menu(app) {
+        Ok(menu) => {
+            let _ = tray.set_menu(Some(menu));
+        }
+        Err(e) => eprintln!("Failed to rebuild tray menu: {}", e),
+    }
+}
@@ -0,0 +1,193 @@
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use tauri::AppHandle;
+
+use crate::paste;
+use crate::recording;
+use crate::state;
+use crate::text::normalize;
+
+/// What a spoken command does instead of pasting the dictation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum CommandAction {
+    /// Press a key chord such as `"Enter"` or `"CmdOrCtrl+Z"` in the app
+    /// that was active before recording
+    Keys { keys: String },
+    SwitchProfile { profile: String },
+    /// Run a shell command. The dictation is passed in the
+    /// `LIGHTWHISPER_TEXT` environment variable.
+    Shell { command: String },
+    /// Remove the last pasted dictation
+    UndoLast,
+}
+
+/// A phrase, e.g. "press enter", and what saying it does.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VoiceCommand {
+    pub phrase: String,
+    #[serde(flatten)]
+    pub action: CommandAction,
+}
+
+impl VoiceCommand {
+    fn keys(phrase: &str, keys: &str) -> Self {
+        Self {
+            phrase: phrase.to_string(),
+            action: CommandAction::Keys {
+                keys: keys.to_string(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VoiceCommands {
+    /// Word that turns a dictation into a command, e.g. "computer" in
+    /// "computer press enter". Empty to only recognize commands dictated
+    /// with the command shortcut.
+    pub prefix: String,
+    pub commands: Vec<VoiceCommand>,
+}
+
+impl Default for VoiceCommands {
+    fn default() -> Self {
+        Self {
+            prefix: String::new(),
+            commands: vec![
+                VoiceCommand::keys("press enter", "Enter"),
+                VoiceCommand::keys("undo that", "CmdOrCtrl+Z"),
+                VoiceCommand::keys("select all", "CmdOrCtrl+A"),
+                VoiceCommand {
+                    phrase: "scratch that".to_string(),
+                    action: CommandAction::UndoLast,
+                },
+            ],
+        }
+    }
+}
+
+/// The command `text` asks for, if any. With `forced` (the command shortcut
+/// started the dictation) the whole text must be a command and anything else
+/// is an error. Otherwise only text starting with the prefix word is looked
+/// up, and is dictated as usual if it names no command.
+pub fn find<'a>(
+    settings: &'a VoiceCommands,
+    text: &str,
+    forced: bool,
+) -> Result<Option<&'a VoiceCommand>, String> {
+    let text = normalize(text);
+    let prefix = normalize(&settings.prefix);
+    let phrase = if forced {
+        text.as_str()
+    } else if prefix.is_empty() {
+        return Ok(None);
+    } else {
+        match text.strip_prefix(&prefix).and_then(|rest| rest.strip_prefix(' ')) {
+            Some(rest) => rest,
+            None => return Ok(None),
+        }
+    };
+
+    let command = settings
+        .commands
+        .iter()
+        .find(|c| normalize(&c.phrase) == phrase);
+    match command {
+        Some(command) => Ok(Some(command)),
+        None if forced => Err(format!("Unknown voice command: \"{}\"", phrase)),
+        None => Ok(None),
+    }
+}
+
+/// Carry out `command`. `text` is the dictation that triggered it.
+pub fn run(app: &AppHandle, command: &VoiceCommand, text: &str) -> Result<(), String> {
+    eprintln!("Voice command: {}", command.phrase);
+    match &command.action {
+        CommandAction::Keys { keys } => {
+            recording::restore_focus(app);
+            paste::press_chord(keys)
+        }
+        CommandAction::SwitchProfile { profile } => state::switch_profile(app, profile),
+        CommandAction::Shell { command } => run_shell(command, text),
+        CommandAction::UndoLast => recording::undo_last(app),
+    }
+}
+
+fn run_shell(command: &str, text: &str) -> Result<(), String> {
+    let mut shell = if cfg!(target_os = "windows") {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    let mut child = shell
+        .arg(command)
+        .env("LIGHTWHISPER_TEXT", text)
+        .spawn()
+        .map_err(|e| format!("Failed to run `{}`: {}", command, e))?;
+
+    // Reap the process without holding up the next dictation
+    let command = command.to_string();
+    std::thread::spawn(move || match child.wait() {
+        Ok(status) if !status.success() => eprintln!("`{}` exited with {}", command, status),
+        Ok(_) => {}
+        Err(e) => eprintln!("Failed to wait for `{}`: {}", command, e),
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn phrase(result: Result<Option<&VoiceCommand>, String>) -> Result<Option<&str>, String> {
+        result.map(|c| c.map(|c| c.phrase.as_str()))
+    }
+
+    #[test]
+    fn default_commands() {
+        let settings = VoiceCommands::default();
+        assert_eq!(phrase(find(&settings, "Press enter.", true)), Ok(Some("press enter")));
+        assert_eq!(phrase(find(&settings, "undo that", true)), Ok(Some("undo that")));
+        let scratch = find(&settings, "Scratch that!", true).unwrap().unwrap();
+        assert_eq!(scratch.action, CommandAction::UndoLast);
+    }
+
+    #[test]
+    fn forced_text_must_be_a_command() {
+        let settings = VoiceCommands::default();
+        assert_eq!(
+            phrase(find(&settings, "Press enter twice", true)),
+            Err("Unknown voice command: \"press enter twice\"".to_string())
+        );
+    }
+
+    #[test]
+    fn without_a_prefix_only_forced_dictations_are_commands() {
+        let settings = VoiceCommands::default();
+        assert_eq!(phrase(find(&settings, "press enter", false)), Ok(None));
+    }
+
+    #[test]
+    fn prefix_marks_a_command() {
+        let settings = VoiceCommands {
+            prefix: "Computer".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            phrase(find(&settings, "Computer, press enter.", false)),
+            Ok(Some("press enter"))
+        );
+        // Dictated as usual
+        assert_eq!(phrase(find(&settings, "computer press the button", false)), Ok(None));
+        assert_eq!(phrase(find(&settings, "computers press enter", false)), Ok(None));
+        assert_eq!(phrase(find(&settings, "press enter", false)), Ok(None));
+        // The prefix is not spoken with the command shortcut
+        assert_eq!(phrase(find(&settings, "press enter", true)), Ok(Some("press enter")));
+    }
+}
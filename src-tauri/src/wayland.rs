@@ -0,0 +1,769 @@
+use enigo::Key;
+use serde::Serialize;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+// ── Wayland sessions: enigo's X11 input only reaches XWayland apps, so keys
+//    go through the compositor's virtual-keyboard protocol or ydotool
+//    (uinput). arboard reaches the clipboard through the data-control
+//    protocol, with wl-copy as a fallback ──
+
+/// Whether this is a Wayland session.
+pub fn is_session() -> bool {
+    cfg!(target_os = "linux")
+        && (std::env::var("XDG_SESSION_TYPE").is_ok_and(|t| t.eq_ignore_ascii_case("wayland"))
+            || std::env::var_os("WAYLAND_DISPLAY").is_some())
+}
+
+/// `XDG_CURRENT_DESKTOP`, e.g. `GNOME` or `sway`.
+fn desktop() -> String {
+    std::env::var("XDG_CURRENT_DESKTOP").unwrap_or_default()
+}
+
+/// Interfaces the compositor offers, e.g. `wl_seat`. Empty when there is
+/// no Wayland compositor to ask.
+fn compositor_globals() -> &'static [String] {
+    #[cfg(target_os = "linux")]
+    {
+        virtual_keyboard::globals()
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        &[]
+    }
+}
+
+fn has_global(interface: &str) -> bool {
+    compositor_globals().iter().any(|g| g == interface)
+}
+
+/// Sway, Hyprland and other wlroots compositors have it; GNOME and KDE do
+/// not.
+fn has_virtual_keyboard() -> bool {
+    has_global("zwp_virtual_keyboard_manager_v1")
+}
+
+/// The protocols arboard reads and sets the clipboard with.
+fn has_data_control() -> bool {
+    has_global("ext_data_control_manager_v1") || has_global("zwlr_data_control_manager_v1")
+}
+
+fn find_program(name: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(name))
+        .find(|p| p.is_file())
+}
+
+/// Socket of the ydotoold daemon, which ydotool needs.
+fn ydotool_socket() -> PathBuf {
+    if let Some(socket) = std::env::var_os("YDOTOOL_SOCKET") {
+        return PathBuf::from(socket);
+    }
+    let runtime = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(|dir| PathBuf::from(dir).join(".ydotool_socket"))
+        .filter(|p| p.exists());
+    runtime.unwrap_or_else(|| PathBuf::from("/tmp/.ydotool_socket"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InputTool {
+    /// The compositor's virtual-keyboard protocol
+    VirtualKeyboard,
+    Ydotool,
+}
+
+impl InputTool {
+    /// Why the tool cannot be used, if it cannot.
+    fn problem(&self) -> Option<String> {
+        match self {
+            InputTool::VirtualKeyboard if !has_virtual_keyboard() => {
+                let desktop = desktop();
+                Some(format!(
+                    "{} does not offer the virtual-keyboard protocol",
+                    if desktop.is_empty() { "The compositor" } else { &desktop }
+                ))
+            }
+            InputTool::Ydotool if find_program("ydotool").is_none() => {
+                Some("ydotool is not installed".to_string())
+            }
+            InputTool::Ydotool if !ydotool_socket().exists() => Some(format!(
+                "ydotoold is not running (no socket at {})",
+                ydotool_socket().display()
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// The tool used to press keys, or why there is none.
+pub fn input_tool() -> Result<InputTool, String> {
+    pick_tool(
+        [InputTool::VirtualKeyboard, InputTool::Ydotool]
+            .into_iter()
+            .map(|tool| (tool, tool.problem())),
+    )
+}
+
+/// The first tool without a problem, in order of preference.
+fn pick_tool(
+    tools: impl IntoIterator<Item = (InputTool, Option<String>)>,
+) -> Result<InputTool, String> {
+    let mut problems = Vec::new();
+    for (tool, problem) in tools {
+        match problem {
+            None => return Ok(tool),
+            Some(problem) => problems.push(problem),
+        }
+    }
+    Err(format!(
+        "Cannot press keys on Wayland ({}). Install ydotool and start ydotoold",
+        problems.join("; ")
+    ))
+}
+
+fn run(program: &str, args: &[String], stdin: Option<&str>) -> Result<(), String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(if stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        pipe.write_all(input.as_bytes())
+            .map_err(|e| format!("Failed to write to {}: {}", program, e))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// xkb keysym name of a key, for the virtual keyboard.
+fn keysym(key: Key) -> Option<String> {
+    let name = match key {
+        Key::Return => "Return",
+        Key::Tab => "Tab",
+        Key::Escape => "Escape",
+        Key::Backspace => "BackSpace",
+        Key::Delete => "Delete",
+        Key::Space => "space",
+        Key::UpArrow => "Up",
+        Key::DownArrow => "Down",
+        Key::LeftArrow => "Left",
+        Key::RightArrow => "Right",
+        Key::Home => "Home",
+        Key::End => "End",
+        Key::PageUp => "Prior",
+        Key::PageDown => "Next",
+        Key::F1 => "F1",
+        Key::F2 => "F2",
+        Key::F3 => "F3",
+        Key::F4 => "F4",
+        Key::F5 => "F5",
+        Key::F6 => "F6",
+        Key::F7 => "F7",
+        Key::F8 => "F8",
+        Key::F9 => "F9",
+        Key::F10 => "F10",
+        Key::F11 => "F11",
+        Key::F12 => "F12",
+        Key::Unicode(c) if c.is_ascii_alphanumeric() => return Some(c.to_string()),
+        _ => return None,
+    };
+    Some(name.to_string())
+}
+
+/// xkb modifier mask bit of a modifier key, as in the "complete" keymap
+/// types: Mod1 is Alt and Mod4 the logo key.
+fn modifier_mask(key: Key) -> Option<u32> {
+    match key {
+        Key::Shift => Some(1),
+        Key::Control => Some(4),
+        Key::Alt => Some(8),
+        Key::Meta => Some(64),
+        _ => None,
+    }
+}
+
+/// Linux input event code of a key on a US layout, for ydotool. Event codes
+/// are key positions, so on another layout the compositor turns them into
+/// whatever that layout has there: "Ctrl+Z" presses Ctrl+W on AZERTY.
+fn keycode(key: Key) -> Option<u16> {
+    const LETTERS: &[u16; 26] = &[
+        30, 48, 46, 32, 18, 33, 34, 35, 23, 36, 37, 38, 50, 49, 24, 25, 16, 19, 31, 20, 22, 47,
+        17, 45, 21, 44,
+    ];
+    let code = match key {
+        Key::Control => 29,
+        Key::Shift => 42,
+        Key::Alt => 56,
+        Key::Meta => 125,
+        Key::Return => 28,
+        Key::Tab => 15,
+        Key::Escape => 1,
+        Key::Backspace => 14,
+        Key::Delete => 111,
+        Key::Space => 57,
+        Key::UpArrow => 103,
+        Key::DownArrow => 108,
+        Key::LeftArrow => 105,
+        Key::RightArrow => 106,
+        Key::Home => 102,
+        Key::End => 107,
+        Key::PageUp => 104,
+        Key::PageDown => 109,
+        Key::F1 => 59,
+        Key::F2 => 60,
+        Key::F3 => 61,
+        Key::F4 => 62,
+        Key::F5 => 63,
+        Key::F6 => 64,
+        Key::F7 => 65,
+        Key::F8 => 66,
+        Key::F9 => 67,
+        Key::F10 => 68,
+        Key::F11 => 87,
+        Key::F12 => 88,
+        Key::Unicode(c) => match c.to_ascii_lowercase() {
+            c @ 'a'..='z' => LETTERS[(c as u8 - b'a') as usize],
+            '0' => 11,
+            c @ '1'..='9' => (c as u8 - b'1') as u16 + 2,
+            '-' => 12,
+            '=' => 13,
+            '[' => 26,
+            ']' => 27,
+            ';' => 39,
+            '\'' => 40,
+            '`' => 41,
+            '\\' => 43,
+            ',' => 51,
+            '.' => 52,
+            '/' => 53,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(code)
+}
+
+fn unsupported(key: Key, tool: InputTool) -> String {
+    match tool {
+        InputTool::Ydotool => format!(
+            "{:?} cannot be pressed with ydotool, which only knows the keys of a US layout",
+            key
+        ),
+        InputTool::VirtualKeyboard => {
+            format!("{:?} cannot be pressed with the virtual keyboard", key)
+        }
+    }
+}
+
+/// What the virtual keyboard sends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeyEvent {
+    /// Set the held modifiers to this mask
+    Modifiers(u32),
+    /// Press or release the key with this evdev code
+    Key { code: u32, pressed: bool },
+}
+
+/// Keys for the virtual keyboard: a keymap made of exactly the keysyms
+/// needed, one per key, and the events pressing them.
+#[derive(Debug, Default, PartialEq)]
+struct Typing {
+    /// Keysym names, the first on evdev code 1
+    keysyms: Vec<String>,
+    events: Vec<KeyEvent>,
+}
+
+impl Typing {
+    /// Press and release the key producing `keysym`.
+    fn tap(&mut self, keysym: String) {
+        let index = match self.keysyms.iter().position(|k| *k == keysym) {
+            Some(index) => index,
+            None => {
+                self.keysyms.push(keysym);
+                self.keysyms.len() - 1
+            }
+        };
+        let code = index as u32 + 1;
+        self.events.push(KeyEvent::Key { code, pressed: true });
+        self.events.push(KeyEvent::Key {
+            code,
+            pressed: false,
+        });
+    }
+}
+
+/// Hold all keys but the last, which must be modifiers, and tap the last one
+/// `count` times.
+fn chord_typing(keys: &[Key], count: usize) -> Result<Typing, String> {
+    let Some((&last, modifiers)) = keys.split_last() else {
+        return Ok(Typing::default());
+    };
+    let tool = InputTool::VirtualKeyboard;
+    let mask = modifiers.iter().try_fold(0, |mask, &key| {
+        modifier_mask(key)
+            .map(|bit| mask | bit)
+            .ok_or_else(|| unsupported(key, tool))
+    })?;
+    let keysym = keysym(last).ok_or_else(|| unsupported(last, tool))?;
+
+    let mut typing = Typing::default();
+    if mask != 0 {
+        typing.events.push(KeyEvent::Modifiers(mask));
+    }
+    for _ in 0..count {
+        typing.tap(keysym.clone());
+    }
+    if mask != 0 {
+        typing.events.push(KeyEvent::Modifiers(0));
+    }
+    Ok(typing)
+}
+
+/// One key per character, whatever the user's layout.
+fn text_typing(text: &str) -> Typing {
+    let mut typing = Typing::default();
+    for c in text.chars() {
+        let keysym = match c {
+            '\n' => "Return".to_string(),
+            '\t' => "Tab".to_string(),
+            // "\r\n" is one line break
+            c if c.is_control() => continue,
+            // xkb names a keysym by its code point
+            c => format!("U{:04X}", c as u32),
+        };
+        typing.tap(keysym);
+    }
+    typing
+}
+
+/// xkb keymap putting `keysyms` on evdev codes 1, 2…, which xkb numbers
+/// from 9.
+fn keymap(keysyms: &[String]) -> String {
+    let mut codes = String::new();
+    let mut symbols = String::new();
+    for (i, keysym) in keysyms.iter().enumerate() {
+        codes.push_str(&format!("<K{}> = {};\n", i + 1, i + 9));
+        symbols.push_str(&format!("key <K{}> {{[{}]}};\n", i + 1, keysym));
+    }
+    format!(
+        "xkb_keymap {{\n\
+         xkb_keycodes \"lightwhisper\" {{\nminimum = 8;\nmaximum = {};\n{}}};\n\
+         xkb_types \"lightwhisper\" {{ include \"complete\" }};\n\
+         xkb_compatibility \"lightwhisper\" {{ include \"complete\" }};\n\
+         xkb_symbols \"lightwhisper\" {{\n{}}};\n\
+         }};\n",
+        keysyms.len() + 9,
+        codes,
+        symbols
+    )
+}
+
+/// ydotool arguments holding all keys but the last, tapping the last one
+/// `count` times, then releasing the others.
+fn ydotool_chord_args(keys: &[Key], count: usize) -> Result<Vec<String>, String> {
+    let Some((&last, modifiers)) = keys.split_last() else {
+        return Ok(Vec::new());
+    };
+    let tool = InputTool::Ydotool;
+    let codes = modifiers
+        .iter()
+        .map(|&key| keycode(key).ok_or_else(|| unsupported(key, tool)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let code = keycode(last).ok_or_else(|| unsupported(last, tool))?;
+
+    let mut args = vec!["key".to_string()];
+    args.extend(codes.iter().map(|c| format!("{}:1", c)));
+    for _ in 0..count {
+        args.extend([format!("{}:1", code), format!("{}:0", code)]);
+    }
+    args.extend(codes.iter().rev().map(|c| format!("{}:0", c)));
+    Ok(args)
+}
+
+/// Hold all keys but the last, tap the last one `count` times, then
+/// release the others.
+pub fn press_chord(keys: &[Key], count: usize) -> Result<(), String> {
+    if keys.is_empty() {
+        return Ok(());
+    }
+    match input_tool()? {
+        InputTool::VirtualKeyboard => send_keys(&chord_typing(keys, count)?),
+        InputTool::Ydotool => run("ydotool", &ydotool_chord_args(keys, count)?, None),
+    }
+}
+
+/// Type `text` as key presses.
+pub fn type_text(text: &str) -> Result<(), String> {
+    match input_tool()? {
+        InputTool::VirtualKeyboard => send_keys(&text_typing(text)),
+        // Read from stdin so text starting with `-` is not taken for an option
+        InputTool::Ydotool => run("ydotool", &["type", "--file", "-"].map(String::from), Some(text)),
+    }
+}
+
+fn send_keys(typing: &Typing) -> Result<(), String> {
+    if typing.events.is_empty() {
+        return Ok(());
+    }
+    #[cfg(target_os = "linux")]
+    {
+        virtual_keyboard::send(typing)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        Err("The virtual keyboard is only available on Linux".to_string())
+    }
+}
+
+/// Put `text` on the clipboard with wl-copy, for compositors arboard cannot
+/// reach. wl-copy keeps serving it in the background.
+pub fn copy_text(text: &str) -> Result<(), String> {
+    if find_program("wl-copy").is_none() {
+        return Err("No Wayland clipboard: the compositor lacks the data-control protocol \
+                    and wl-copy (wl-clipboard) is not installed"
+            .to_string());
+    }
+    run("wl-copy", &[], Some(text))
+}
+
+/// Offer `text` with wl-copy until one client has pasted it; wl-copy then
+/// exits, see [`wait_exit`].
+pub fn copy_text_once(text: &str) -> Result<Child, String> {
+    if find_program("wl-copy").is_none() {
+        return Err("No Wayland clipboard: the compositor lacks the data-control protocol \
+                    and wl-copy (wl-clipboard) is not installed"
+            .to_string());
+    }
+    let mut child = Command::new("wl-copy")
+        .args(["--foreground", "--paste-once"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to run wl-copy: {}", e))?;
+    // Closing stdin tells wl-copy the text is complete
+    if let Some(mut pipe) = child.stdin.take() {
+        if let Err(e) = pipe.write_all(text.as_bytes()) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("Failed to write to wl-copy: {}", e));
+        }
+    }
+    Ok(child)
+}
+
+/// Wait up to `timeout` for `child` to exit. Returns whether it exited
+/// successfully in time.
+pub fn wait_exit(child: &mut Child, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        match child.try_wait() {
+            Ok(Some(status)) => return status.success(),
+            Ok(None) => std::thread::sleep(Duration::from_millis(10)),
+            Err(_) => return false,
+        }
+    }
+    false
+}
+
+/// The clipboard text, read with wl-paste.
+pub fn read_text() -> Option<String> {
+    let output = Command::new("wl-paste")
+        .arg("--no-newline")
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// What the settings window shows about Wayland support.
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostics {
+    pub wayland: bool,
+    pub desktop: String,
+    /// Tool used to press keys, if any
+    pub input: Option<InputTool>,
+    /// Whether the compositor offers a data-control protocol
+    pub data_control: bool,
+    /// Whether arboard can open the clipboard (data-control or XWayland)
+    pub clipboard: bool,
+    pub wl_copy: bool,
+    pub problems: Vec<String>,
+}
+
+pub fn diagnose() -> Diagnostics {
+    let wayland = is_session();
+    let input = input_tool();
+    let clipboard = arboard::Clipboard::new().is_ok();
+    let wl_copy = find_program("wl-copy").is_some();
+
+    let mut problems = Vec::new();
+    if wayland {
+        if let Err(e) = &input {
+            problems.push(e.clone());
+        }
+        if !clipboard && !wl_copy {
+            problems.push(
+                "No clipboard access: the compositor lacks the data-control protocol and \
+                 there is no XWayland; install wl-clipboard"
+                    .to_string(),
+            );
+        }
+    }
+
+    Diagnostics {
+        wayland,
+        desktop: desktop(),
+        input: input.ok(),
+        data_control: has_data_control(),
+        clipboard,
+        wl_copy,
+        problems,
+    }
+}
+
+/// Client for the virtual-keyboard protocol, the one wtype uses.
+#[cfg(target_os = "linux")]
+mod virtual_keyboard {
+    use std::fs::File;
+    use std::io::Write;
+    use std::os::fd::{AsFd, FromRawFd};
+    use std::sync::OnceLock;
+    use std::time::Instant;
+    use wayland_client::globals::{registry_queue_init, GlobalListContents};
+    use wayland_client::protocol::wl_registry::{self, WlRegistry};
+    use wayland_client::protocol::wl_seat::WlSeat;
+    use wayland_client::{delegate_noop, Connection, Dispatch, QueueHandle};
+
+    use self::protocol::zwp_virtual_keyboard_manager_v1::ZwpVirtualKeyboardManagerV1;
+    use self::protocol::zwp_virtual_keyboard_v1::ZwpVirtualKeyboardV1;
+    use super::{keymap, KeyEvent, Typing};
+
+    mod protocol {
+        // The generated code refers to `super::wayland_client`
+        #[allow(clippy::single_component_path_imports)]
+        use wayland_client;
+        use wayland_client::protocol::*;
+
+        pub mod __interfaces {
+            use wayland_client::backend as wayland_backend;
+            use wayland_client::protocol::__interfaces::*;
+            wayland_scanner::generate_interfaces!("protocols/virtual-keyboard-unstable-v1.xml");
+        }
+        use self::__interfaces::*;
+
+        wayland_scanner::generate_client_code!("protocols/virtual-keyboard-unstable-v1.xml");
+    }
+
+    /// `wl_keyboard.keymap_format.xkb_v1`
+    const KEYMAP_XKB_V1: u32 = 1;
+
+    struct Client;
+
+    impl Dispatch<WlRegistry, GlobalListContents> for Client {
+        fn event(
+            _: &mut Self,
+            _: &WlRegistry,
+            _: wl_registry::Event,
+            _: &GlobalListContents,
+            _: &Connection,
+            _: &QueueHandle<Self>,
+        ) {
+        }
+    }
+
+    delegate_noop!(Client: ignore WlSeat);
+    delegate_noop!(Client: ZwpVirtualKeyboardManagerV1);
+    delegate_noop!(Client: ZwpVirtualKeyboardV1);
+
+    /// Interfaces the compositor offers, asked once: they do not change
+    /// during a session.
+    pub fn globals() -> &'static [String] {
+        static GLOBALS: OnceLock<Vec<String>> = OnceLock::new();
+        GLOBALS.get_or_init(|| {
+            let Ok(conn) = Connection::connect_to_env() else {
+                return Vec::new();
+            };
+            match registry_queue_init::<Client>(&conn) {
+                Ok((globals, _)) => globals
+                    .contents()
+                    .with_list(|list| list.iter().map(|g| g.interface.clone()).collect()),
+                Err(_) => Vec::new(),
+            }
+        })
+    }
+
+    /// The keymap in an anonymous file, NUL-terminated as the compositor
+    /// expects.
+    fn keymap_file(keymap: &str) -> Result<File, String> {
+        let fd = unsafe { libc::memfd_create(c"lightwhisper-keymap".as_ptr(), libc::MFD_CLOEXEC) };
+        if fd < 0 {
+            return Err(format!(
+                "Failed to create the keymap: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        let mut file = unsafe { File::from_raw_fd(fd) };
+        file.write_all(keymap.as_bytes())
+            .and_then(|_| file.write_all(&[0]))
+            .map_err(|e| format!("Failed to write the keymap: {}", e))?;
+        Ok(file)
+    }
+
+    /// Create a virtual keyboard on the first seat and send `typing`.
+    pub fn send(typing: &Typing) -> Result<(), String> {
+        let conn = Connection::connect_to_env()
+            .map_err(|e| format!("Failed to connect to the compositor: {}", e))?;
+        let (globals, mut queue) = registry_queue_init::<Client>(&conn)
+            .map_err(|e| format!("Failed to query the compositor: {}", e))?;
+        let qh = queue.handle();
+        let seat: WlSeat = globals
+            .bind(&qh, 1..=1, ())
+            .map_err(|e| format!("No seat to type on: {}", e))?;
+        let manager: ZwpVirtualKeyboardManagerV1 = globals
+            .bind(&qh, 1..=1, ())
+            .map_err(|e| format!("No virtual-keyboard protocol: {}", e))?;
+        let keyboard = manager.create_virtual_keyboard(&seat, &qh, ());
+
+        let keymap = keymap(&typing.keysyms);
+        let file = keymap_file(&keymap)?;
+        keyboard.keymap(KEYMAP_XKB_V1, file.as_fd(), keymap.len() as u32 + 1);
+
+        let start = Instant::now();
+        for event in &typing.events {
+            match *event {
+                KeyEvent::Modifiers(mask) => keyboard.modifiers(mask, 0, 0, 0),
+                KeyEvent::Key { code, pressed } => {
+                    keyboard.key(start.elapsed().as_millis() as u32, code, pressed as u32)
+                }
+            }
+        }
+        keyboard.destroy();
+        // Returns once the compositor has handled every request
+        queue
+            .roundtrip(&mut Client)
+            .map_err(|e| format!("Failed to send keys: {}", e))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_tables() {
+        assert_eq!(keysym(Key::Return).as_deref(), Some("Return"));
+        assert_eq!(keysym(Key::PageUp).as_deref(), Some("Prior"));
+        assert_eq!(keysym(Key::Unicode('v')).as_deref(), Some("v"));
+        assert_eq!(keysym(Key::Unicode('é')), None);
+
+        assert_eq!(keycode(Key::Unicode('a')), Some(30));
+        assert_eq!(keycode(Key::Unicode('Q')), Some(16));
+        assert_eq!(keycode(Key::Unicode('z')), Some(44));
+        assert_eq!(keycode(Key::Unicode('1')), Some(2));
+        assert_eq!(keycode(Key::Unicode('0')), Some(11));
+        assert_eq!(keycode(Key::F11), Some(87));
+        assert_eq!(keycode(Key::Unicode('é')), None);
+    }
+
+    #[test]
+    fn ydotool_holds_modifiers_around_taps() {
+        assert_eq!(
+            ydotool_chord_args(&[Key::Control, Key::Shift, Key::Unicode('z')], 1).unwrap(),
+            ["key", "29:1", "42:1", "44:1", "44:0", "42:0", "29:0"]
+        );
+        assert_eq!(
+            ydotool_chord_args(&[Key::Backspace], 2).unwrap(),
+            ["key", "14:1", "14:0", "14:1", "14:0"]
+        );
+        assert!(ydotool_chord_args(&[Key::Control, Key::Unicode('é')], 1)
+            .unwrap_err()
+            .contains("US layout"));
+    }
+
+    #[test]
+    fn virtual_keyboard_chords_set_modifiers() {
+        let pressed = |code| KeyEvent::Key { code, pressed: true };
+        let released = |code| KeyEvent::Key {
+            code,
+            pressed: false,
+        };
+        assert_eq!(
+            chord_typing(&[Key::Control, Key::Unicode('v')], 1).unwrap(),
+            Typing {
+                keysyms: vec!["v".to_string()],
+                events: vec![
+                    KeyEvent::Modifiers(4),
+                    pressed(1),
+                    released(1),
+                    KeyEvent::Modifiers(0)
+                ],
+            }
+        );
+        assert_eq!(
+            chord_typing(&[Key::Backspace], 2).unwrap().events,
+            [pressed(1), released(1), pressed(1), released(1)]
+        );
+        // Only modifiers can be held
+        assert!(chord_typing(&[Key::Unicode('a'), Key::Unicode('b')], 1).is_err());
+    }
+
+    #[test]
+    fn virtual_keyboard_types_any_character() {
+        let typing = text_typing("é-é\r\n");
+        assert_eq!(typing.keysyms, ["U00E9", "U002D", "Return"]);
+        let codes: Vec<u32> = typing
+            .events
+            .iter()
+            .filter_map(|e| match e {
+                KeyEvent::Key {
+                    code,
+                    pressed: true,
+                } => Some(*code),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(codes, [1, 2, 1, 3]);
+
+        let keymap = keymap(&typing.keysyms);
+        assert!(keymap.contains("maximum = 12;"));
+        assert!(keymap.contains("<K1> = 9;\n<K2> = 10;\n<K3> = 11;\n"));
+        assert!(keymap.contains("key <K3> {[Return]};"));
+    }
+
+    #[test]
+    fn first_working_tool_is_picked() {
+        use InputTool::*;
+        assert_eq!(pick_tool([(VirtualKeyboard, None), (Ydotool, None)]), Ok(VirtualKeyboard));
+        assert_eq!(
+            pick_tool([(VirtualKeyboard, Some("no protocol".to_string())), (Ydotool, None)]),
+            Ok(Ydotool)
+        );
+        let error = pick_tool([
+            (VirtualKeyboard, Some("no protocol".to_string())),
+            (Ydotool, Some("ydotool is not installed".to_string())),
+        ])
+        .unwrap_err();
+        assert!(error.contains("(no protocol; ydotool is not installed)"));
+    }
+}
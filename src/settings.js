@@ -14,12 +14,25 @@ function showToast(message) {
 
 event.listen('app-error', (e) => {
   showToast(e.payload);
+}).then(async () => {
+  // Problems found before this page could listen
+  const notices = await invoke('take_startup_notices');
+  notices.forEach(showToast);
+});
+
+// config.json was edited outside the app
+event.listen('config-changed', () => {
+  loadConfig();
 });
 
 const audioDeviceEl = document.getElementById('audio-device');
 const engineEl = document.getElementById('engine');
 const modelSizeEl = document.getElementById('model-size');
 const whisperOptionsEl = document.getElementById('whisper-options');
+const remoteOptionsEl = document.getElementById('remote-options');
+const remoteUrlEl = document.getElementById('remote-url');
+const remoteModelEl = document.getElementById('remote-model');
+const remoteApiKeyEl = document.getElementById('remote-api-key');
 const languageEl = document.getElementById('language');
 const downloadBtn = document.getElementById('download-btn');
 const saveBtn = document.getElementById('save-btn');
@@ -27,25 +40,121 @@ const modelStatusEl = document.getElementById('model-status');
 const progressContainer = document.getElementById('progress-container');
 const progressFill = document.getElementById('progress-fill');
 const progressText = document.getElementById('progress-text');
-const shortcutDisplay = document.getElementById('shortcut-display');
-const shortcutAssignBtn = document.getElementById('shortcut-assign-btn');
+const bindingsEl = document.getElementById('bindings');
+const bindingAddBtn = document.getElementById('binding-add-btn');
 const shortcutDefaultBtn = document.getElementById('shortcut-default-btn');
 const shortcutError = document.getElementById('shortcut-error');
+const fallbackEngineEl = document.getElementById('fallback-engine');
+const fallbackOptionsEl = document.getElementById('fallback-options');
+const fallbackModelSizeEl = document.getElementById('fallback-model-size');
+const fallbackEagerEl = document.getElementById('fallback-eager');
+const promptEl = document.getElementById('prompt');
+const hallucinationFilterEl = document.getElementById('hallucination-filter');
+const transformsEl = document.getElementById('transforms');
+const transformTypeEl = document.getElementById('transform-type');
+const transformAddBtn = document.getElementById('transform-add-btn');
+const previewInputEl = document.getElementById('preview-input');
+const previewOutputEl = document.getElementById('preview-output');
+const dictRulesEl = document.getElementById('dictionary-rules');
+const dictFindEl = document.getElementById('dict-find');
+const dictReplaceEl = document.getElementById('dict-replace');
+const dictAddBtn = document.getElementById('dict-add-btn');
+const dictPathEl = document.getElementById('dict-path');
+const dictImportBtn = document.getElementById('dict-import-btn');
+const dictExportBtn = document.getElementById('dict-export-btn');
+const dictReplaceAllEl = document.getElementById('dict-replace-all');
+const dictionaryError = document.getElementById('dictionary-error');
+const snippetsEl = document.getElementById('snippets');
+const snippetTriggerEl = document.getElementById('snippet-trigger');
+const snippetTemplateEl = document.getElementById('snippet-template');
+const snippetAddBtn = document.getElementById('snippet-add-btn');
+const snippetsError = document.getElementById('snippets-error');
+const undoMethodEl = document.getElementById('undo-method');
+const insertModeEl = document.getElementById('insert-mode');
+const outputClipboardEl = document.getElementById('output-clipboard');
+const outputStdoutEl = document.getElementById('output-stdout');
+const outputFileEl = document.getElementById('output-file');
+const outputFifoEl = document.getElementById('output-fifo');
+const historyEnabledEl = document.getElementById('history-enabled');
+const historyMaxEl = document.getElementById('history-max');
+const commandPrefixEl = document.getElementById('command-prefix');
+const voiceCommandsEl = document.getElementById('voice-commands');
+const commandAddBtn = document.getElementById('command-add-btn');
+const profileEl = document.getElementById('profile');
+const profileNameEl = document.getElementById('profile-name');
+const profileAddBtn = document.getElementById('profile-add-btn');
+const profileDeleteBtn = document.getElementById('profile-delete-btn');
+const profileError = document.getElementById('profile-error');
 
 function updateWhisperOptionsVisibility() {
-  whisperOptionsEl.style.display = engineEl.value === 'whisper' ? '' : 'none';
+  // The remote engine takes a prompt too, but has no model size or filter
+  whisperOptionsEl.style.display = ['whisper', 'remote'].includes(engineEl.value) ? '' : 'none';
+  document.querySelectorAll('.whisper-only').forEach(el => {
+    el.style.display = engineEl.value === 'whisper' ? '' : 'none';
+  });
+  updateRemoteOptionsVisibility();
+}
+
+function updateFallbackOptionsVisibility() {
+  fallbackOptionsEl.style.display = fallbackEngineEl.value ? '' : 'none';
+  fallbackModelSizeEl.style.display = fallbackEngineEl.value === 'whisper' ? '' : 'none';
+  updateRemoteOptionsVisibility();
+}
+
+function updateRemoteOptionsVisibility() {
+  const used = engineEl.value === 'remote' || fallbackEngineEl.value === 'remote';
+  remoteOptionsEl.style.display = used ? '' : 'none';
+}
+
+function activeProfile(config) {
+  return config.profiles.find(p => p.name === config.active_profile) || config.profiles[0];
+}
+
+function renderProfiles(config) {
+  profileEl.innerHTML = '';
+  config.profiles.forEach(p => {
+    const opt = document.createElement('option');
+    opt.value = p.name;
+    opt.textContent = p.name;
+    profileEl.appendChild(opt);
+  });
+  profileEl.value = config.active_profile;
+  profileDeleteBtn.disabled = config.profiles.length <= 1;
 }
 
 async function loadConfig() {
   try {
     const config = await invoke('get_config');
-    engineEl.value = config.engine || 'whisper';
-    modelSizeEl.value = config.model_size || 'base';
-    languageEl.value = config.language || 'auto';
+    const profile = activeProfile(config);
+    renderProfiles(config);
+    engineEl.value = profile.engine || 'whisper';
+    modelSizeEl.value = profile.model_size || 'base';
+    languageEl.value = profile.language || 'auto';
+    promptEl.value = profile.prompt || '';
+    hallucinationFilterEl.checked = (profile.hallucination_filter || {}).enabled !== false;
+    transforms = (profile.postprocess || []).map(t => ({ ...t }));
+    drawTransforms();
+    fallbackEngineEl.value = config.fallback_engine || '';
+    fallbackModelSizeEl.value = config.fallback_model_size || 'base';
+    fallbackEagerEl.checked = !!config.fallback_eager;
+    const remote = config.remote || {};
+    remoteUrlEl.value = remote.url || '';
+    remoteModelEl.value = remote.model || '';
+    remoteApiKeyEl.value = remote.api_key || '';
 
-    shortcutDisplay.value = formatShortcutDisplay(config.shortcut || 'Alt+Space');
+    renderBindings(config);
+    renderVoiceCommands(config);
+    renderAppRules(config);
+    undoMethodEl.value = config.undo_method || 'backspace';
+    renderOutputs(config.outputs || [{ type: 'paste' }]);
+    const history = config.history || {};
+    historyEnabledEl.checked = history.enabled !== false;
+    historyMaxEl.value = history.max_entries ?? 1000;
+    loadDictionary(config);
+    loadSnippets();
 
     updateWhisperOptionsVisibility();
+    updateFallbackOptionsVisibility();
 
     // Load audio devices
     const devices = await invoke('list_audio_devices');
@@ -56,8 +165,8 @@ async function loadConfig() {
       opt.textContent = d;
       audioDeviceEl.appendChild(opt);
     });
-    if (config.audio_device && config.audio_device !== 'default') {
-      audioDeviceEl.value = config.audio_device;
+    if (profile.audio_device && profile.audio_device !== 'default') {
+      audioDeviceEl.value = profile.audio_device;
     }
 
     // Check model status
@@ -74,8 +183,20 @@ async function checkModelStatus() {
       engine: engine,
       modelSize: modelSizeEl.value,
     });
+    downloadBtn.style.display = engine === 'remote' ? 'none' : '';
+    if (engine === 'remote') {
+      // Nothing to download; the server is only contacted when dictating
+      modelStatusEl.textContent = 'Recordings are sent to the transcription server.';
+      modelStatusEl.style.color = '#50c878';
+      return;
+    }
     const label = engine === 'parakeet' ? 'Parakeet TDT v3' : `Whisper "${modelSizeEl.value}"`;
-    if (exists) {
+    const status = await invoke('get_engine_status');
+    if (exists && status.loading) {
+      modelStatusEl.textContent = `${label} is downloaded, loading...`;
+      modelStatusEl.style.color = '#ffcc44';
+      downloadBtn.textContent = 'Re-download Model';
+    } else if (exists) {
       modelStatusEl.textContent = `${label} is downloaded and ready.`;
       modelStatusEl.style.color = '#50c878';
       downloadBtn.textContent = 'Re-download Model';
@@ -97,6 +218,8 @@ engineEl.addEventListener('change', () => {
 
 modelSizeEl.addEventListener('change', checkModelStatus);
 
+fallbackEngineEl.addEventListener('change', updateFallbackOptionsVisibility);
+
 downloadBtn.addEventListener('click', async () => {
   downloadBtn.disabled = true;
   progressContainer.classList.remove('hidden');
@@ -140,28 +263,549 @@ event.listen('download-error', (e) => {
   downloadBtn.disabled = false;
 });
 
+// ── Engine loading ──
+
+function engineLabel(payload) {
+  if (payload.engine === 'remote') return 'remote server';
+  return payload.engine === 'parakeet' ? 'Parakeet TDT v3' : `Whisper "${payload.model_size}"`;
+}
+
+event.listen('engine-loading', (e) => {
+  modelStatusEl.textContent = `Loading ${engineLabel(e.payload)}...`;
+  modelStatusEl.style.color = '#ffcc44';
+});
+
+event.listen('engine-ready', () => {
+  checkModelStatus();
+});
+
+event.listen('engine-error', (e) => {
+  showToast(`Failed to load ${engineLabel(e.payload)}: ${e.payload.error}`);
+  checkModelStatus();
+});
+
+// Copy the form values into the active profile of `config`
+function applyFormToConfig(config) {
+  const profile = activeProfile(config);
+  profile.audio_device = audioDeviceEl.value;
+  profile.engine = engineEl.value;
+  profile.model_size = modelSizeEl.value;
+  profile.language = languageEl.value;
+  profile.prompt = promptEl.value;
+  profile.hallucination_filter = { ...profile.hallucination_filter, enabled: hallucinationFilterEl.checked };
+  profile.postprocess = transforms;
+  config.fallback_engine = fallbackEngineEl.value || null;
+  config.fallback_model_size = fallbackModelSizeEl.value;
+  config.fallback_eager = fallbackEagerEl.checked;
+  config.remote = {
+    ...config.remote,
+    url: remoteUrlEl.value.trim(),
+    model: remoteModelEl.value.trim(),
+    api_key: remoteApiKeyEl.value,
+  };
+  config.undo_method = undoMethodEl.value;
+  config.outputs = outputsFromForm();
+  config.history = {
+    enabled: historyEnabledEl.checked,
+    max_entries: Math.max(0, parseInt(historyMaxEl.value, 10) || 0),
+  };
+  config.voice_commands = { prefix: commandPrefixEl.value.trim(), commands: voiceCommands };
+  config.app_rules = appRules;
+  return config;
+}
+
+// ── Outputs ──
+
+function renderOutputs(outputs) {
+  const find = type => outputs.find(o => o.type === type);
+  const insert = outputs.find(o => o.type === 'paste' || o.type === 'type');
+  insertModeEl.value = insert ? insert.type : '';
+  outputClipboardEl.checked = !!find('clipboard');
+  outputStdoutEl.checked = !!find('stdout');
+  outputFileEl.value = (find('file') || {}).path || '';
+  outputFifoEl.value = (find('fifo') || {}).path || '';
+}
+
+function outputsFromForm() {
+  const outputs = [];
+  if (insertModeEl.value) outputs.push({ type: insertModeEl.value });
+  if (outputClipboardEl.checked) outputs.push({ type: 'clipboard' });
+  if (outputStdoutEl.checked) outputs.push({ type: 'stdout' });
+  const file = outputFileEl.value.trim();
+  if (file) outputs.push({ type: 'file', path: file });
+  const fifo = outputFifoEl.value.trim();
+  if (fifo) outputs.push({ type: 'fifo', path: fifo });
+  return outputs;
+}
+
+function showSaveError(e) {
+  if (e && e.shortcut) {
+    // Nothing was changed; e.shortcut.kind is invalid, conflict or unavailable
+    shortcutError.textContent = e.shortcut.message;
+  }
+  if (e && e.fields && e.fields.length) {
+    e.fields.forEach(f => showToast(`${f.field}: ${f.message}`));
+  } else {
+    showToast(e && e.message ? e.message : String(e));
+  }
+}
+
 saveBtn.addEventListener('click', async () => {
   try {
-    // Read current config to preserve shortcut (managed separately)
-    const current = await invoke('get_config');
-    await invoke('save_config', {
-      config: {
-        audio_device: audioDeviceEl.value,
-        model_size: modelSizeEl.value,
-        language: languageEl.value,
-        engine: engineEl.value,
-        shortcut: current.shortcut || 'Alt+Space',
-      }
-    });
+    // Start from the saved config so settings managed elsewhere (shortcuts,
+    // other profiles) are preserved
+    const config = applyFormToConfig(await invoke('get_config'));
+    await invoke('save_config', { config });
     saveBtn.textContent = 'Saved!';
     setTimeout(() => { saveBtn.textContent = 'Save Settings'; }, 1500);
   } catch (e) {
     console.error('Failed to save:', e);
+    showSaveError(e);
     saveBtn.textContent = 'Error saving';
     setTimeout(() => { saveBtn.textContent = 'Save Settings'; }, 2000);
   }
 });
 
+// ── Profiles ──
+
+profileEl.addEventListener('change', async () => {
+  profileError.textContent = '';
+  try {
+    await invoke('switch_profile', { name: profileEl.value });
+  } catch (e) {
+    profileError.textContent = String(e);
+  }
+  loadConfig();
+});
+
+profileAddBtn.addEventListener('click', async () => {
+  const name = profileNameEl.value.trim();
+  profileError.textContent = '';
+  if (!name) return;
+
+  try {
+    const config = await invoke('get_config');
+    if (config.profiles.some(p => p.name === name)) {
+      profileError.textContent = `A profile named "${name}" already exists.`;
+      return;
+    }
+    // New profiles start as a copy of the form
+    const profile = { ...activeProfile(applyFormToConfig(config)), name };
+    config.profiles.push(profile);
+    config.active_profile = name;
+    await invoke('save_config', { config });
+    profileNameEl.value = '';
+    loadConfig();
+  } catch (e) {
+    showSaveError(e);
+  }
+});
+
+profileDeleteBtn.addEventListener('click', async () => {
+  profileError.textContent = '';
+  try {
+    const config = await invoke('get_config');
+    if (config.profiles.length <= 1) return;
+    config.profiles = config.profiles.filter(p => p.name !== config.active_profile);
+    config.active_profile = config.profiles[0].name;
+    await invoke('save_config', { config });
+    loadConfig();
+  } catch (e) {
+    showSaveError(e);
+  }
+});
+
+// ── Post-processing ──
+
+// Default settings for each transform type, in the order they are offered
+const TRANSFORMS = {
+  normalize_whitespace: { label: 'Normalize whitespace', defaults: {} },
+  capitalize_first: { label: 'Capitalize first letter', defaults: {} },
+  strip_trailing_period: { label: 'Strip trailing period', defaults: {} },
+  inverse_text_normalization: { label: 'Numbers, dates and units', defaults: {} },
+  remove_fillers: { label: 'Remove fillers and repeats', defaults: { words: [], collapse_repeats: true } },
+  spoken_punctuation: { label: 'Spoken punctuation', defaults: { rules: [] } },
+  case: { label: 'Change case', defaults: { case: 'lower' } },
+  wrap: { label: 'Add prefix / suffix', defaults: { prefix: '', suffix: ' ' } },
+  llm_edit: {
+    label: 'LLM post-edit',
+    defaults: {
+      endpoint: 'http://localhost:8080/v1/chat/completions',
+      model: '',
+      prompt: 'Fix grammar, spelling and punctuation in this dictated text. Keep its wording and language.\n\n{text}',
+      timeout_ms: 10000,
+    },
+  },
+};
+
+const LLM_PROMPTS = {
+  'Fix grammar': 'Fix grammar, spelling and punctuation in this dictated text. Keep its wording and language.\n\n{text}',
+  'Professional tone': 'Rewrite this dictated text in a clear, professional tone, in the same language.\n\n{text}',
+  'Bullet list': 'Format this dictated text as a concise bullet list, one "- " item per point, in the same language.\n\n{text}',
+};
+
+// Chain being edited for the active profile; saved with the other settings
+let transforms = [];
+
+Object.entries(TRANSFORMS).forEach(([type, t]) => {
+  const opt = document.createElement('option');
+  opt.value = type;
+  opt.textContent = t.label;
+  transformTypeEl.appendChild(opt);
+});
+
+function transformSettings(transform) {
+  const inputs = [];
+  if (transform.type === 'case') {
+    const select = document.createElement('select');
+    ['lower', 'upper'].forEach(c => {
+      const opt = document.createElement('option');
+      opt.value = c;
+      opt.textContent = c;
+      select.appendChild(opt);
+    });
+    select.value = transform.case;
+    select.addEventListener('change', () => { transform.case = select.value; updatePreview(); });
+    inputs.push(select);
+  } else if (transform.type === 'spoken_punctuation' && transform.rules.length) {
+    // Custom phrases are edited in config.json
+    const note = document.createElement('span');
+    note.textContent = `+${transform.rules.length} custom`;
+    inputs.push(note);
+  } else if (transform.type === 'remove_fillers') {
+    const label = document.createElement('label');
+    label.className = 'checkbox-label';
+    const box = document.createElement('input');
+    box.type = 'checkbox';
+    box.checked = transform.collapse_repeats !== false;
+    box.addEventListener('change', () => { transform.collapse_repeats = box.checked; updatePreview(); });
+    label.append(box, 'repeats');
+    inputs.push(label);
+  } else if (transform.type === 'llm_edit') {
+    // Not run by the preview, which updates on every keystroke
+    const preset = document.createElement('select');
+    [['', 'Custom prompt'], ...Object.keys(LLM_PROMPTS).map(k => [k, k])].forEach(([value, label]) => {
+      const opt = document.createElement('option');
+      opt.value = value;
+      opt.textContent = label;
+      preset.appendChild(opt);
+    });
+    preset.value = Object.keys(LLM_PROMPTS).find(k => LLM_PROMPTS[k] === transform.prompt) || '';
+    inputs.push(preset);
+
+    const details = [['endpoint', 'Endpoint URL'], ['model', 'Model (optional)']].map(([key, placeholder]) => {
+      const input = document.createElement('input');
+      input.type = 'text';
+      input.className = 'text-input transform-detail';
+      input.placeholder = placeholder;
+      input.value = transform[key];
+      input.addEventListener('change', () => { transform[key] = input.value; });
+      return input;
+    });
+    const prompt = document.createElement('textarea');
+    prompt.className = 'text-input transform-detail';
+    prompt.rows = 3;
+    prompt.value = transform.prompt;
+    prompt.addEventListener('change', () => { transform.prompt = prompt.value; preset.value = ''; });
+    preset.addEventListener('change', () => {
+      if (!preset.value) return;
+      transform.prompt = LLM_PROMPTS[preset.value];
+      prompt.value = transform.prompt;
+    });
+    inputs.push(...details, prompt);
+  } else if (transform.type === 'wrap') {
+    ['prefix', 'suffix'].forEach(key => {
+      const input = document.createElement('input');
+      input.type = 'text';
+      input.className = 'text-input';
+      input.placeholder = key;
+      input.value = transform[key];
+      input.addEventListener('input', () => { transform[key] = input.value; updatePreview(); });
+      inputs.push(input);
+    });
+  }
+  return inputs;
+}
+
+function drawTransforms() {
+  transformsEl.innerHTML = '';
+  transforms.forEach((transform, i) => {
+    const row = document.createElement('div');
+    row.className = 'transform-row';
+
+    const enabled = document.createElement('input');
+    enabled.type = 'checkbox';
+    enabled.checked = transform.enabled !== false;
+    enabled.addEventListener('change', () => { transform.enabled = enabled.checked; updatePreview(); });
+    row.appendChild(enabled);
+
+    const name = document.createElement('span');
+    name.className = 'transform-name';
+    name.textContent = TRANSFORMS[transform.type] ? TRANSFORMS[transform.type].label : transform.type;
+    row.appendChild(name);
+
+    transformSettings(transform).forEach(input => row.appendChild(input));
+
+    const move = (delta, label) => {
+      const btn = document.createElement('button');
+      btn.type = 'button';
+      btn.textContent = label;
+      btn.disabled = i + delta < 0 || i + delta >= transforms.length;
+      btn.addEventListener('click', () => {
+        [transforms[i], transforms[i + delta]] = [transforms[i + delta], transforms[i]];
+        drawTransforms();
+      });
+      row.appendChild(btn);
+    };
+    move(-1, '↑');
+    move(1, '↓');
+
+    const remove = document.createElement('button');
+    remove.type = 'button';
+    remove.textContent = '✕';
+    remove.addEventListener('click', () => {
+      transforms.splice(i, 1);
+      drawTransforms();
+    });
+    row.appendChild(remove);
+
+    transformsEl.appendChild(row);
+  });
+  updatePreview();
+}
+
+async function updatePreview() {
+  const text = previewInputEl.value;
+  if (!text) {
+    previewOutputEl.textContent = '';
+    return;
+  }
+  try {
+    previewOutputEl.textContent = await invoke('preview_postprocess', {
+      text,
+      transforms,
+      language: languageEl.value,
+    });
+  } catch (e) {
+    previewOutputEl.textContent = String(e);
+  }
+}
+
+transformAddBtn.addEventListener('click', () => {
+  const type = transformTypeEl.value;
+  transforms.push({ type, enabled: true, ...TRANSFORMS[type].defaults });
+  drawTransforms();
+});
+
+previewInputEl.addEventListener('input', updatePreview);
+languageEl.addEventListener('change', updatePreview);
+
+// ── Dictionary ──
+
+function scopeSelect(options, value, onChange) {
+  const select = document.createElement('select');
+  options.forEach(([v, label]) => {
+    const opt = document.createElement('option');
+    opt.value = v;
+    opt.textContent = label;
+    select.appendChild(opt);
+  });
+  select.value = value || '';
+  select.addEventListener('change', () => onChange(select.value || null));
+  return select;
+}
+
+async function updateRule(rule) {
+  dictionaryError.textContent = '';
+  try {
+    await invoke('update_dictionary_rule', { rule });
+    updatePreview();
+  } catch (e) {
+    dictionaryError.textContent = String(e);
+  }
+}
+
+async function loadDictionary(config) {
+  let rules;
+  try {
+    rules = await invoke('list_dictionary');
+  } catch (e) {
+    dictionaryError.textContent = String(e);
+    return;
+  }
+
+  const languages = [['', 'Any language'], ...Array.from(languageEl.options)
+    .filter(o => o.value !== 'auto')
+    .map(o => [o.value, o.textContent])];
+  const profiles = [['', 'Any profile'], ...config.profiles.map(p => [p.name, p.name])];
+
+  dictRulesEl.innerHTML = '';
+  rules.forEach(rule => {
+    const card = document.createElement('div');
+    card.className = 'dict-rule';
+
+    const top = document.createElement('div');
+    top.className = 'inline-row';
+    ['find', 'replace'].forEach(key => {
+      const input = document.createElement('input');
+      input.type = 'text';
+      input.className = 'text-input';
+      input.value = rule[key];
+      input.addEventListener('change', () => { rule[key] = input.value; updateRule(rule); });
+      top.appendChild(input);
+    });
+    const remove = document.createElement('button');
+    remove.type = 'button';
+    remove.textContent = '✕';
+    remove.addEventListener('click', async () => {
+      try {
+        await invoke('delete_dictionary_rule', { id: rule.id });
+      } catch (e) {
+        dictionaryError.textContent = String(e);
+      }
+      loadDictionary(config);
+    });
+    top.appendChild(remove);
+    card.appendChild(top);
+
+    const bottom = document.createElement('div');
+    bottom.className = 'inline-row';
+    [['regex', 'Regex'], ['case_sensitive', 'Match case']].forEach(([key, text]) => {
+      const label = document.createElement('label');
+      label.className = 'checkbox-label';
+      const box = document.createElement('input');
+      box.type = 'checkbox';
+      box.checked = rule[key];
+      box.addEventListener('change', () => { rule[key] = box.checked; updateRule(rule); });
+      label.append(box, text);
+      bottom.appendChild(label);
+    });
+    bottom.appendChild(scopeSelect(languages, rule.language, v => { rule.language = v; updateRule(rule); }));
+    bottom.appendChild(scopeSelect(profiles, rule.profile, v => { rule.profile = v; updateRule(rule); }));
+    card.appendChild(bottom);
+
+    dictRulesEl.appendChild(card);
+  });
+}
+
+function flashButton(btn, text, restore) {
+  btn.textContent = text;
+  setTimeout(() => { btn.textContent = restore; }, 1500);
+}
+
+async function reloadDictionary() {
+  loadDictionary(await invoke('get_config'));
+  updatePreview();
+}
+
+dictAddBtn.addEventListener('click', async () => {
+  dictionaryError.textContent = '';
+  const rule = { find: dictFindEl.value, replace: dictReplaceEl.value };
+  try {
+    await invoke('add_dictionary_rule', { rule });
+    dictFindEl.value = '';
+    dictReplaceEl.value = '';
+    reloadDictionary();
+  } catch (e) {
+    dictionaryError.textContent = String(e);
+  }
+});
+
+dictImportBtn.addEventListener('click', async () => {
+  dictionaryError.textContent = '';
+  try {
+    const count = await invoke('import_dictionary', {
+      path: dictPathEl.value.trim(),
+      replace: dictReplaceAllEl.checked,
+    });
+    flashButton(dictImportBtn, `Imported ${count}`, 'Import');
+    reloadDictionary();
+  } catch (e) {
+    dictionaryError.textContent = String(e);
+  }
+});
+
+dictExportBtn.addEventListener('click', async () => {
+  dictionaryError.textContent = '';
+  try {
+    const count = await invoke('export_dictionary', { path: dictPathEl.value.trim() });
+    flashButton(dictExportBtn, `Exported ${count}`, 'Export');
+  } catch (e) {
+    dictionaryError.textContent = String(e);
+  }
+});
+
+// ── Snippets ──
+
+async function updateSnippet(snippet) {
+  snippetsError.textContent = '';
+  try {
+    await invoke('update_snippet', { snippet });
+  } catch (e) {
+    snippetsError.textContent = String(e);
+  }
+}
+
+async function loadSnippets() {
+  let snippets;
+  try {
+    snippets = await invoke('list_snippets');
+  } catch (e) {
+    snippetsError.textContent = String(e);
+    return;
+  }
+
+  snippetsEl.innerHTML = '';
+  snippets.forEach(snippet => {
+    const card = document.createElement('div');
+    card.className = 'snippet';
+
+    const top = document.createElement('div');
+    top.className = 'inline-row';
+    const trigger = document.createElement('input');
+    trigger.type = 'text';
+    trigger.className = 'text-input';
+    trigger.value = snippet.trigger;
+    trigger.addEventListener('change', () => { snippet.trigger = trigger.value; updateSnippet(snippet); });
+    top.appendChild(trigger);
+    const remove = document.createElement('button');
+    remove.type = 'button';
+    remove.textContent = '✕';
+    remove.addEventListener('click', async () => {
+      try {
+        await invoke('delete_snippet', { id: snippet.id });
+      } catch (e) {
+        snippetsError.textContent = String(e);
+      }
+      loadSnippets();
+    });
+    top.appendChild(remove);
+    card.appendChild(top);
+
+    const template = document.createElement('textarea');
+    template.className = 'text-input';
+    template.rows = 2;
+    template.value = snippet.template;
+    template.addEventListener('change', () => { snippet.template = template.value; updateSnippet(snippet); });
+    card.appendChild(template);
+
+    snippetsEl.appendChild(card);
+  });
+}
+
+snippetAddBtn.addEventListener('click', async () => {
+  snippetsError.textContent = '';
+  const snippet = { trigger: snippetTriggerEl.value, template: snippetTemplateEl.value };
+  try {
+    await invoke('add_snippet', { snippet });
+    snippetTriggerEl.value = '';
+    snippetTemplateEl.value = '';
+    loadSnippets();
+  } catch (e) {
+    snippetsError.textContent = String(e);
+  }
+});
+
 // ── Shortcut ──
 
 function formatShortcutDisplay(shortcut) {
@@ -188,47 +832,141 @@ function codeToKey(code) {
   return map[code] || code;
 }
 
-let isListening = false;
+const DEFAULT_BINDINGS = [
+  { shortcut: 'Alt+Space', action: 'toggle' },
+  { shortcut: 'Escape', action: 'cancel' },
+];
 
-function startListening() {
-  isListening = true;
-  shortcutDisplay.value = 'Press a key combo...';
-  shortcutDisplay.classList.add('listening');
-  shortcutAssignBtn.textContent = 'Cancel';
-  shortcutError.textContent = '';
-}
+const ACTIONS = {
+  toggle: 'Start / stop dictation',
+  push_to_talk: 'Push to talk (hold)',
+  cancel: 'Cancel recording',
+  repaste_last: 'Paste last dictation again',
+  translate: 'Dictate and translate to English',
+  dictate_language: 'Dictate in language',
+  switch_profile: 'Switch profile',
+  command: 'Voice command',
+  undo_last: 'Undo last dictation',
+};
+
+let bindings = [];
+let profileNames = [];
+// Index of the binding waiting for a key combo, or null
+let listeningIndex = null;
 
-function stopListening() {
-  isListening = false;
-  shortcutDisplay.classList.remove('listening');
-  shortcutAssignBtn.textContent = 'Assign';
+function renderBindings(config) {
+  bindings = config.bindings.map(b => ({ ...b }));
+  profileNames = config.profiles.map(p => p.name);
+  drawBindings();
 }
 
-shortcutAssignBtn.addEventListener('click', () => {
-  if (isListening) {
-    // Cancel — restore current config value
-    stopListening();
-    invoke('get_config').then(config => {
-      shortcutDisplay.value = formatShortcutDisplay(config.shortcut || 'Alt+Space');
+function drawBindings() {
+  bindingsEl.innerHTML = '';
+  bindings.forEach((binding, i) => {
+    const row = document.createElement('div');
+    row.className = 'binding-row';
+
+    const display = document.createElement('input');
+    display.type = 'text';
+    display.readOnly = true;
+    display.className = 'shortcut-display';
+    if (listeningIndex === i) {
+      display.value = 'Press a key combo...';
+      display.classList.add('listening');
+    } else {
+      display.value = formatShortcutDisplay(binding.shortcut);
+    }
+    display.addEventListener('click', () => {
+      listeningIndex = listeningIndex === i ? null : i;
+      shortcutError.textContent = '';
+      drawBindings();
     });
-  } else {
-    startListening();
-  }
-});
+    row.appendChild(display);
+
+    const action = document.createElement('select');
+    Object.entries(ACTIONS).forEach(([value, label]) => {
+      const opt = document.createElement('option');
+      opt.value = value;
+      opt.textContent = label;
+      action.appendChild(opt);
+    });
+    action.value = binding.action;
+    action.addEventListener('change', () => {
+      bindings[i] = { shortcut: binding.shortcut, action: action.value };
+      if (action.value === 'switch_profile') bindings[i].profile = profileNames[0];
+      if (action.value === 'dictate_language') bindings[i].language = 'en';
+      saveBindings();
+    });
+    row.appendChild(action);
+
+    if (binding.action === 'switch_profile') {
+      const target = document.createElement('select');
+      profileNames.forEach(name => {
+        const opt = document.createElement('option');
+        opt.value = name;
+        opt.textContent = name;
+        target.appendChild(opt);
+      });
+      target.value = binding.profile;
+      target.addEventListener('change', () => {
+        binding.profile = target.value;
+        saveBindings();
+      });
+      row.appendChild(target);
+    } else if (binding.action === 'dictate_language') {
+      const target = document.createElement('select');
+      Array.from(languageEl.options)
+        .filter(o => o.value !== 'auto')
+        .forEach(o => target.appendChild(o.cloneNode(true)));
+      target.value = binding.language;
+      target.addEventListener('change', () => {
+        binding.language = target.value;
+        saveBindings();
+      });
+      row.appendChild(target);
+    }
 
-shortcutDefaultBtn.addEventListener('click', async () => {
-  stopListening();
+    const remove = document.createElement('button');
+    remove.type = 'button';
+    remove.textContent = 'Remove';
+    remove.addEventListener('click', () => {
+      bindings.splice(i, 1);
+      listeningIndex = null;
+      saveBindings();
+    });
+    row.appendChild(remove);
+
+    bindingsEl.appendChild(row);
+  });
+}
+
+async function saveBindings() {
   shortcutError.textContent = '';
   try {
-    await invoke('change_shortcut', { shortcut: 'Alt+Space' });
-    shortcutDisplay.value = formatShortcutDisplay('Alt+Space');
+    await invoke('set_bindings', { bindings });
   } catch (e) {
-    shortcutError.textContent = String(e);
+    // Nothing was changed; e.kind is invalid, conflict, unavailable or save
+    shortcutError.textContent = e && e.message ? e.message : String(e);
   }
+  // Show what is actually in effect
+  renderBindings(await invoke('get_config'));
+}
+
+bindingAddBtn.addEventListener('click', () => {
+  bindings.push({ shortcut: '', action: 'toggle' });
+  listeningIndex = bindings.length - 1;
+  shortcutError.textContent = '';
+  drawBindings();
 });
 
-document.addEventListener('keydown', async (e) => {
-  if (!isListening) return;
+shortcutDefaultBtn.addEventListener('click', () => {
+  listeningIndex = null;
+  bindings = DEFAULT_BINDINGS.map(b => ({ ...b }));
+  saveBindings();
+});
+
+document.addEventListener('keydown', (e) => {
+  if (listeningIndex === null) return;
   e.preventDefault();
   e.stopPropagation();
 
@@ -243,16 +981,242 @@ document.addEventListener('keydown', async (e) => {
 
   parts.push(codeToKey(e.code));
 
-  const shortcut = parts.join('+');
-  stopListening();
-  shortcutDisplay.value = formatShortcutDisplay(shortcut);
-  shortcutError.textContent = '';
+  bindings[listeningIndex].shortcut = parts.join('+');
+  listeningIndex = null;
+  saveBindings();
+});
+
+// ── Voice commands ──
+
+const COMMAND_ACTIONS = {
+  keys: ['Press keys', 'keys', 'Keys, e.g. CmdOrCtrl+Z'],
+  switch_profile: ['Switch profile', 'profile', ''],
+  shell: ['Run shell command', 'command', 'Command, gets $LIGHTWHISPER_TEXT'],
+  undo_last: ['Undo last dictation', null, ''],
+};
+
+// Commands being edited; saved with the other settings
+let voiceCommands = [];
+
+function renderVoiceCommands(config) {
+  commandPrefixEl.value = config.voice_commands.prefix;
+  voiceCommands = config.voice_commands.commands.map(c => ({ ...c }));
+  drawVoiceCommands();
+}
+
+function drawVoiceCommands() {
+  voiceCommandsEl.innerHTML = '';
+  voiceCommands.forEach((command, i) => {
+    const row = document.createElement('div');
+    row.className = 'inline-row';
+
+    const phrase = document.createElement('input');
+    phrase.type = 'text';
+    phrase.className = 'text-input';
+    phrase.placeholder = 'Phrase, e.g. press enter';
+    phrase.value = command.phrase;
+    phrase.addEventListener('change', () => { command.phrase = phrase.value; });
+    row.appendChild(phrase);
+
+    const action = document.createElement('select');
+    Object.entries(COMMAND_ACTIONS).forEach(([value, [label]]) => {
+      const opt = document.createElement('option');
+      opt.value = value;
+      opt.textContent = label;
+      action.appendChild(opt);
+    });
+    action.value = command.action;
+    action.addEventListener('change', () => {
+      const [, key] = COMMAND_ACTIONS[action.value];
+      voiceCommands[i] = { phrase: command.phrase, action: action.value };
+      if (key) voiceCommands[i][key] = action.value === 'switch_profile' ? profileNames[0] : '';
+      drawVoiceCommands();
+    });
+    row.appendChild(action);
+
+    const [, key, placeholder] = COMMAND_ACTIONS[command.action];
+    let target;
+    if (!key) {
+      target = null;
+    } else if (command.action === 'switch_profile') {
+      target = document.createElement('select');
+      profileNames.forEach(name => {
+        const opt = document.createElement('option');
+        opt.value = name;
+        opt.textContent = name;
+        target.appendChild(opt);
+      });
+    } else {
+      target = document.createElement('input');
+      target.type = 'text';
+      target.className = 'text-input';
+      target.placeholder = placeholder;
+    }
+    if (target) {
+      target.value = command[key];
+      target.addEventListener('change', () => { command[key] = target.value; });
+      row.appendChild(target);
+    }
+
+    const remove = document.createElement('button');
+    remove.type = 'button';
+    remove.textContent = '✕';
+    remove.addEventListener('click', () => {
+      voiceCommands.splice(i, 1);
+      drawVoiceCommands();
+    });
+    row.appendChild(remove);
+
+    voiceCommandsEl.appendChild(row);
+  });
+}
+
+commandAddBtn.addEventListener('click', () => {
+  voiceCommands.push({ phrase: '', action: 'keys', keys: '' });
+  drawVoiceCommands();
+});
+
+// ── App rules ──
+
+const appRulesEl = document.getElementById('app-rules');
+const appRuleAddBtn = document.getElementById('app-rule-add-btn');
+
+// Rules being edited; saved with the other settings
+let appRules = [];
+
+function renderAppRules(config) {
+  appRules = (config.app_rules || []).map(r => ({ ...r }));
+  drawAppRules();
+}
+
+function drawAppRules() {
+  appRulesEl.innerHTML = '';
+  appRules.forEach((rule, i) => {
+    const box = document.createElement('div');
+    box.className = 'app-rule';
+
+    const top = document.createElement('div');
+    top.className = 'inline-row';
+
+    const app = document.createElement('input');
+    app.type = 'text';
+    app.className = 'text-input';
+    app.placeholder = 'App: process, WM_CLASS or bundle id';
+    app.value = rule.app;
+    app.addEventListener('change', () => { rule.app = app.value.trim(); });
+    top.appendChild(app);
+
+    const chord = document.createElement('input');
+    chord.type = 'text';
+    chord.className = 'text-input';
+    chord.placeholder = 'Paste with, e.g. Ctrl+Shift+V';
+    chord.value = rule.paste_chord || '';
+    chord.addEventListener('change', () => { rule.paste_chord = chord.value.trim() || null; });
+    top.appendChild(chord);
+
+    const remove = document.createElement('button');
+    remove.type = 'button';
+    remove.textContent = '✕';
+    remove.addEventListener('click', () => {
+      appRules.splice(i, 1);
+      drawAppRules();
+    });
+    top.appendChild(remove);
+    box.appendChild(top);
+
+    const bottom = document.createElement('div');
+    bottom.className = 'inline-row';
 
+    const profile = document.createElement('select');
+    [['', 'Active profile\'s post-processing'], ...profileNames.map(n => [n, `Post-process as ${n}`])]
+      .forEach(([value, label]) => {
+        const opt = document.createElement('option');
+        opt.value = value;
+        opt.textContent = label;
+        profile.appendChild(opt);
+      });
+    profile.value = rule.postprocess_profile || '';
+    profile.addEventListener('change', () => { rule.postprocess_profile = profile.value || null; });
+    bottom.appendChild(profile);
+
+    const undo = document.createElement('select');
+    [['', 'Undo as in Shortcuts'], ['backspace', 'Undo with Backspaces'], ['undo_chord', 'Undo with the undo shortcut']]
+      .forEach(([value, label]) => {
+        const opt = document.createElement('option');
+        opt.value = value;
+        opt.textContent = label;
+        undo.appendChild(opt);
+      });
+    undo.value = rule.undo_method || '';
+    undo.addEventListener('change', () => { rule.undo_method = undo.value || null; });
+    bottom.appendChild(undo);
+
+    [['type_instead_of_paste', 'Type'], ['strip_trailing_period', 'No final period']]
+      .forEach(([key, text]) => {
+        const label = document.createElement('label');
+        label.className = 'checkbox-label';
+        const check = document.createElement('input');
+        check.type = 'checkbox';
+        check.checked = !!rule[key];
+        check.addEventListener('change', () => { rule[key] = check.checked; });
+        label.append(check, text);
+        bottom.appendChild(label);
+      });
+    box.appendChild(bottom);
+
+    appRulesEl.appendChild(box);
+  });
+}
+
+appRuleAddBtn.addEventListener('click', () => {
+  appRules.push({
+    app: '',
+    paste_chord: null,
+    type_instead_of_paste: false,
+    postprocess_profile: null,
+    strip_trailing_period: false,
+    undo_method: null,
+  });
+  drawAppRules();
+});
+
+// ── Wayland diagnostics ──
+
+const waylandSectionEl = document.getElementById('wayland-section');
+const waylandStatusEl = document.getElementById('wayland-status');
+const waylandCheckBtn = document.getElementById('wayland-check-btn');
+
+async function checkWayland() {
+  let d;
   try {
-    await invoke('change_shortcut', { shortcut });
-  } catch (err) {
-    shortcutError.textContent = String(err);
+    d = await invoke('wayland_diagnostics');
+  } catch (e) {
+    console.error('Failed to check Wayland support:', e);
+    return;
   }
-});
+  waylandSectionEl.classList.toggle('hidden', !d.wayland);
+  if (!d.wayland) return;
+
+  const lines = [
+    `Desktop: ${d.desktop || 'unknown'}`,
+    `Keys: ${{ virtual_keyboard: 'virtual keyboard', ydotool: 'ydotool' }[d.input] || 'no working tool'}`,
+    `Clipboard: ${d.data_control ? 'data-control' : d.clipboard ? 'XWayland' : d.wl_copy ? 'wl-copy' : 'unavailable'}`,
+  ];
+  waylandStatusEl.innerHTML = '';
+  lines.forEach(text => {
+    const line = document.createElement('div');
+    line.textContent = text;
+    waylandStatusEl.appendChild(line);
+  });
+  d.problems.forEach(text => {
+    const line = document.createElement('div');
+    line.className = 'problem';
+    line.textContent = text;
+    waylandStatusEl.appendChild(line);
+  });
+}
+
+waylandCheckBtn.addEventListener('click', checkWayland);
 
 loadConfig();
+checkWayland();